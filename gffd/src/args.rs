@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 #[derive(Parser, Debug)]
 #[command(version,about,long_about = None)]
 pub struct Args {
@@ -44,9 +45,173 @@ pub enum Subcommands {
         id: String,
     },
     ShowConfig {},
+    Config {
+        #[clap(subcommand)]
+        action: ConfigAction,
+    },
+    /// Add the screens GFT has to the config file, list its showing badges that are
+    /// neither strands nor badges, and list the ones screenings use that are still missing
+    Discover {
+        /// Only list what would be added
+        #[arg(long = "dry-run", default_value_t = false)]
+        dry_run: bool,
+    },
     List {},
     Upload {},
     Sync {},
+    Export {
+        #[clap(subcommand)]
+        format: ExportFormat,
+    },
+    Render {
+        #[clap(subcommand)]
+        document: RenderDocument,
+    },
+    /// Render the next version into publish_directory, unless nothing has changed
+    Publish {
+        #[arg(value_enum)]
+        document: PublishDocument,
+        /// A directory of templates that replace the built in ones with the same name
+        #[arg(long = "templates", short = 't', default_value_t = ("").to_string())]
+        templates: String,
+        /// Put the day grids before the brochure listings
+        #[arg(long = "with-grid", default_value_t = false)]
+        grid: bool,
+    },
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum ConfigAction {
+    /// Look for mistakes serde lets through, such as duplicate ids and bad colours
+    Check {},
+    /// Write a JSON Schema for the config file, for editors to check and complete it with
+    Schema {
+        #[arg(long = "output", short = 'o', default_value_t = ("").to_string())]
+        output: String,
+    },
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum RenderDocument {
+    Brochure {
+        #[clap(flatten)]
+        options: RenderOptions,
+        #[arg(long = "profile", value_enum, default_value_t = PosterProfile::Print)]
+        profile: PosterProfile,
+        /// Put the day grids before the listings
+        #[arg(long = "with-grid", default_value_t = false)]
+        grid: bool,
+        #[arg(long = "format", value_enum, default_value_t = BrochureFormat::Pdf)]
+        format: BrochureFormat,
+    },
+    Summary {
+        #[clap(flatten)]
+        options: RenderOptions,
+    },
+    /// The day grids with a row for each person, rather than each screen
+    PersonSummary {
+        #[clap(flatten)]
+        options: RenderOptions,
+    },
+    FilterSummary {
+        #[clap(flatten)]
+        options: RenderOptions,
+        /// Only these people or groups, by name
+        #[arg(long = "person")]
+        people: Vec<String>,
+        /// Write a separate pdf for each person and group
+        #[arg(long = "per-person", default_value_t = false)]
+        per_person: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BrochureFormat {
+    Pdf,
+    /// A single page with the posters inlined, to search and filter on a phone
+    Html,
+    Epub,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PublishDocument {
+    /// The print brochure
+    Brochure,
+    /// The filtered summaries for everyone
+    Summaries,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PosterProfile {
+    /// Posters at 300 dpi for printing
+    Print,
+    /// Smaller, more compressed posters for a pdf to read on a phone
+    Phone,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RenderOptions {
+    /// Defaults to <document>.pdf in the current directory
+    #[arg(long = "output", short = 'o', default_value_t = ("").to_string())]
+    pub output: String,
+    /// The version shown in the page footers, defaults to the next one to publish
+    #[arg(long = "doc-version", default_value_t = ("").to_string())]
+    pub doc_version: String,
+    /// A directory of templates that replace the built in ones with the same name
+    #[arg(long = "templates", short = 't', default_value_t = ("").to_string())]
+    pub templates: String,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum ExportFormat {
+    Ics {
+        #[clap(flatten)]
+        filter: ExportFilter,
+        #[arg(long = "output", short = 'o', default_value_t = ("").to_string())]
+        output: String,
+    },
+    Csv {
+        #[clap(flatten)]
+        filter: ExportFilter,
+        #[arg(long = "output", short = 'o', default_value_t = ("").to_string())]
+        output: String,
+        #[arg(long = "layout", value_enum, default_value_t = CsvLayout::Google)]
+        layout: CsvLayout,
+        /// Pick columns by header name, overriding the layout
+        #[arg(long = "column")]
+        columns: Vec<String>,
+    },
+    Xlsx {
+        #[clap(flatten)]
+        filter: ExportFilter,
+        #[arg(long = "output", short = 'o', default_value_t = ("gff-2026.xlsx").to_string())]
+        output: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CsvLayout {
+    /// Subject, Start Date, Start Time... for importing into Google Calendar
+    Google,
+    /// One wide row per screening for the planning spreadsheet
+    Sheet,
+}
+
+#[derive(Parser, Debug, Clone, Default)]
+pub struct ExportFilter {
+    #[arg(long = "strand")]
+    pub strands: Vec<String>,
+    #[arg(long = "screen")]
+    pub screens: Vec<String>,
+    #[arg(long = "from")]
+    pub from: Option<NaiveDate>,
+    #[arg(long = "to")]
+    pub to: Option<NaiveDate>,
+    #[arg(long = "person")]
+    pub people: Vec<String>,
+    // Screening attributes by name or symbol, such as captioned or CC
+    #[arg(long = "badge")]
+    pub badges: Vec<String>,
 }
 
 impl Args {
@@ -1,29 +1,44 @@
+use crate::films::{festival_instant, TIME_ZONE};
 use crate::Config;
 use crate::FestivalEvent;
 use chrono::{DateTime, Duration};
 use google_calendar3::api::{Channel, Event, EventDateTime, Scope};
-use google_calendar3::{CalendarHub, Error};
+use google_calendar3::CalendarHub;
 use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::client::legacy::Client;
 use std::collections::HashMap;
 use std::fs;
+use thiserror::Error;
 use yup_oauth2::{parse_service_account_key, ServiceAccountAuthenticator};
 
+#[derive(Error, Debug)]
+pub enum CalendarError {
+    // The service account file can't be read or used
+    #[error("Unable to authenticate with {0} - {1}")]
+    Auth(String, String),
+    // A request to Google Calendar failed
+    #[error("Google Calendar request failed - {0}")]
+    Request(#[from] google_calendar3::Error),
+    // Unable to make the https connector
+    #[error("Unable to connect to Google Calendar - {0}")]
+    Connect(String),
+}
+
 enum EventType {
     Main,
     Filter,
 }
 
-async fn hub(cfg: &Config) -> CalendarHub<HttpsConnector<HttpConnector>> {
-    let service_credentials =
-        fs::read_to_string(&cfg.calendar_auth_file).expect("Unable to read auth file");
+async fn hub(cfg: &Config) -> Result<CalendarHub<HttpsConnector<HttpConnector>>, CalendarError> {
+    let auth_error =
+        |e: std::io::Error| CalendarError::Auth(cfg.calendar_auth_file.clone(), e.to_string());
+    let service_credentials = fs::read_to_string(&cfg.calendar_auth_file).map_err(auth_error)?;
 
-    let service_key =
-        parse_service_account_key(service_credentials).expect("Bad gmail credentials");
+    let service_key = parse_service_account_key(service_credentials).map_err(auth_error)?;
     let https = HttpsConnectorBuilder::new()
         .with_native_roots()
-        .unwrap()
+        .map_err(|e| CalendarError::Connect(e.to_string()))?
         .https_only()
         .enable_http2()
         .build();
@@ -33,9 +48,8 @@ async fn hub(cfg: &Config) -> CalendarHub<HttpsConnector<HttpConnector>> {
     let auth = ServiceAccountAuthenticator::builder(service_key)
         .build()
         .await
-        .expect("Failed to create an authenticator");
-    let hub = CalendarHub::new(client, auth);
-    hub
+        .map_err(auth_error)?;
+    Ok(CalendarHub::new(client, auth))
 }
 
 trait Gff {
@@ -100,13 +114,16 @@ impl Gff for Event {
     }
 }
 
-pub async fn sync_events(cfg: &Config, _festival_events: &[FestivalEvent]) -> (u32, u32) {
-    let hub = hub(cfg).await;
+pub async fn sync_events(
+    cfg: &Config,
+    _festival_events: &[FestivalEvent],
+) -> Result<(u32, u32), CalendarError> {
+    let hub = hub(cfg).await?;
     let mut to_delete = vec![];
     let mut to_modify = vec![];
 
     let main_events = events(&hub, &cfg.calendar_main_id)
-        .await
+        .await?
         .into_iter()
         .filter(|e| e.get_screening_id() != 0)
         .filter(|e| {
@@ -119,7 +136,7 @@ pub async fn sync_events(cfg: &Config, _festival_events: &[FestivalEvent]) -> (u
         .map(|e| (e.get_screening_id(), e))
         .collect::<HashMap<u32, Event>>();
     let filter_events = events(&hub, &cfg.calendar_filter_id)
-        .await
+        .await?
         .into_iter()
         .filter(|e| e.get_screening_id() != 0)
         .map(|e| (e.get_screening_id(), e))
@@ -149,8 +166,7 @@ pub async fn sync_events(cfg: &Config, _festival_events: &[FestivalEvent]) -> (u
             hub.events()
                 .insert(evt.clone(), &cfg.calendar_filter_id)
                 .doit()
-                .await
-                .unwrap();
+                .await?;
         } else {
             hub.events()
                 .update(
@@ -159,8 +175,7 @@ pub async fn sync_events(cfg: &Config, _festival_events: &[FestivalEvent]) -> (u
                     &evt.id.as_ref().cloned().unwrap(),
                 )
                 .doit()
-                .await
-                .unwrap();
+                .await?;
         }
     }
 
@@ -168,20 +183,22 @@ pub async fn sync_events(cfg: &Config, _festival_events: &[FestivalEvent]) -> (u
         hub.events()
             .delete(&cfg.calendar_filter_id, &evt.id.as_ref().cloned().unwrap())
             .doit()
-            .await
-            .unwrap();
+            .await?;
     }
     println!("Update: {:?}", &to_modify);
     println!("Delete: {:?}", &to_delete);
-    (to_modify.len() as u32, to_delete.len() as u32)
+    Ok((to_modify.len() as u32, to_delete.len() as u32))
 }
 
-pub async fn upload_events(cfg: &Config, festival_events: &[FestivalEvent]) -> (u32, u32) {
-    let hub = hub(cfg).await;
+pub async fn upload_events(
+    cfg: &Config,
+    festival_events: &[FestivalEvent],
+) -> Result<(u32, u32), CalendarError> {
+    let hub = hub(cfg).await?;
     let mut to_delete = vec![];
     let mut to_update = vec![];
     let remote_events = events(&hub, &cfg.calendar_main_id)
-        .await
+        .await?
         .into_iter()
         .filter(|e| e.get_screening_id() != 0)
         .map(|e| (e.get_screening_id(), e))
@@ -216,8 +233,7 @@ pub async fn upload_events(cfg: &Config, festival_events: &[FestivalEvent]) -> (
             hub.events()
                 .insert(evt.clone(), &cfg.calendar_main_id)
                 .doit()
-                .await
-                .unwrap();
+                .await?;
         } else {
             hub.events()
                 .update(
@@ -226,8 +242,7 @@ pub async fn upload_events(cfg: &Config, festival_events: &[FestivalEvent]) -> (
                     &evt.id.as_ref().cloned().unwrap(),
                 )
                 .doit()
-                .await
-                .unwrap();
+                .await?;
         }
     }
 
@@ -235,12 +250,11 @@ pub async fn upload_events(cfg: &Config, festival_events: &[FestivalEvent]) -> (
         hub.events()
             .delete(&cfg.calendar_main_id, &evt.id.as_ref().cloned().unwrap())
             .doit()
-            .await
-            .unwrap();
+            .await?;
     }
     println!("Update: {:?}", &to_update);
     println!("Delete: {:?}", &to_delete);
-    (to_update.len() as u32, to_delete.len() as u32)
+    Ok((to_update.len() as u32, to_delete.len() as u32))
 }
 
 fn filter_events_match(a: &Event, b: &Event) -> bool {
@@ -280,21 +294,7 @@ fn main_events_match(a: &Event, b: &Event) -> bool {
 }
 
 fn initials(desc: &str) -> String {
-    let mut names = vec![];
-    let mut name = "".to_string();
-    for c in desc.chars() {
-        if c.is_alphabetic() {
-            name.push(c);
-        } else if !name.is_empty() {
-            names.push(name);
-            name = "".to_string();
-        }
-    }
-
-    if !name.is_empty() {
-        names.push(name);
-    }
-    names
+    attendees(desc)
         .iter()
         .map(|e| match &e[..] {
             "Patrick" => "Pt".to_string(),
@@ -323,25 +323,28 @@ fn filter_event_from(evt: &Event) -> Event {
 
 fn main_event_from(evt: &FestivalEvent) -> Event {
     let mut cal: Event = Default::default();
-    let time_zone = chrono::Utc;
-    let start_date_time = evt
-        .date
-        .and_time(evt.start)
-        .and_local_timezone(chrono::Utc)
-        .unwrap();
-    let mut end_date_time = evt
-        .date
-        .and_time(evt.end)
-        .and_local_timezone(chrono::Utc)
-        .unwrap();
-    if evt.end < evt.start {
-        end_date_time += std::time::Duration::from_hours(24);
-    }
+    let time_zone = TIME_ZONE.name();
+    // Blocked out until any Q&A is over
+    let (start, end) = evt.span();
+    let start_date_time = festival_instant(start);
+    let end_date_time = festival_instant(end);
     cal.summary = Some(evt.title.clone());
-    cal.location = if evt.screen.is_empty() {
+    // The block runs on for any Q&A, so the film's own running time goes with the screen.
+    // Not in the description, which holds who is going.
+    let running_time = if evt.extra_minutes == 0 {
+        "".to_string()
+    } else {
+        format!("({})", evt.running_time())
+    };
+    let location = [evt.screen.as_str(), running_time.as_str()]
+        .into_iter()
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    cal.location = if location.is_empty() {
         None
     } else {
-        Some(evt.screen.clone())
+        Some(location)
     };
     cal.start = Some(EventDateTime {
         date: None,
@@ -358,14 +361,17 @@ fn main_event_from(evt: &FestivalEvent) -> Event {
     cal
 }
 
-pub async fn events(hub: &CalendarHub<HttpsConnector<HttpConnector>>, id: &str) -> Vec<Event> {
+pub async fn events(
+    hub: &CalendarHub<HttpsConnector<HttpConnector>>,
+    id: &str,
+) -> Result<Vec<Event>, CalendarError> {
     let query = hub
         .events()
         .list(id)
         .time_min(chrono::Utc::now() - chrono::Duration::days(21))
         .add_scope(Scope::EventReadonly);
     let mut result = vec![];
-    let mut response = query.doit().await.unwrap();
+    let mut response = query.doit().await?;
     loop {
         if let Some(mut items) = response.1.items {
             result.append(&mut items);
@@ -377,44 +383,125 @@ pub async fn events(hub: &CalendarHub<HttpsConnector<HttpConnector>>, id: &str)
                 .add_scope(Scope::EventReadonly)
                 .page_token(&token)
                 .doit()
-                .await
-                .unwrap()
+                .await?
         } else {
             break;
         }
     }
-    result
+    Ok(result)
 }
 
-pub async fn filter_summary(cfg: &Config) -> Vec<FestivalEvent> {
-    let hub = hub(cfg).await;
-    events(&hub, &cfg.calendar_filter_id)
-        .await
+pub async fn filter_summary(cfg: &Config) -> Result<Vec<FestivalEvent>, CalendarError> {
+    let hub = hub(cfg).await?;
+    Ok(events(&hub, &cfg.calendar_filter_id)
+        .await?
         .into_iter()
         .map(|e| e.into())
+        .collect())
+}
+
+/// Split a main calendar event description into the names of the people going.
+pub fn attendees(desc: &str) -> Vec<String> {
+    desc.split(|c: char| !c.is_alphabetic())
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_string())
         .collect()
 }
 
+/// Add or remove a person from a list of attendees, keeping the original order.
+pub fn toggle_attendee(current: &[String], person: &str, going: bool) -> Vec<String> {
+    // Already going, so nothing to change
+    if going && current.iter().any(|e| e == person) {
+        return current.to_vec();
+    }
+    let mut result = current
+        .iter()
+        .filter(|e| *e != person)
+        .cloned()
+        .collect::<Vec<_>>();
+    if going {
+        result.push(person.to_string());
+    }
+    result
+}
+
+/// Map each screening id on the main calendar to the people marked as going.
+pub async fn attendance(cfg: &Config) -> Result<HashMap<u32, Vec<String>>, CalendarError> {
+    let hub = hub(cfg).await?;
+    Ok(events(&hub, &cfg.calendar_main_id)
+        .await?
+        .into_iter()
+        .filter(|e| e.get_screening_id() != 0)
+        .map(|e| {
+            (
+                e.get_screening_id(),
+                attendees(&e.description.as_ref().cloned().unwrap_or("".to_owned())),
+            )
+        })
+        .collect())
+}
+
+/// Fill in the attendees of each screening from the main calendar.
+pub async fn fill_attendance(
+    cfg: &Config,
+    festival_events: &mut [FestivalEvent],
+) -> Result<(), CalendarError> {
+    let going = attendance(cfg).await?;
+    for evt in festival_events.iter_mut() {
+        evt.attendees = going.get(&evt.screening_id).cloned().unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Mark a person as going (or not) to a screening on the main calendar and then
+/// bring the filter calendar back into line.  Returns false if the screening isn't
+/// on the main calendar.
+pub async fn set_attendance(
+    cfg: &Config,
+    screening_id: u32,
+    person: &str,
+    going: bool,
+) -> Result<bool, CalendarError> {
+    let hub = hub(cfg).await?;
+    let found = events(&hub, &cfg.calendar_main_id)
+        .await?
+        .into_iter()
+        .find(|e| e.get_screening_id() == screening_id);
+    let Some(mut evt) = found else {
+        return Ok(false);
+    };
+    let current = attendees(&evt.description.as_ref().cloned().unwrap_or("".to_owned()));
+    let updated = toggle_attendee(&current, person, going);
+    if updated == current {
+        return Ok(true);
+    }
+    evt.description = if updated.is_empty() {
+        None
+    } else {
+        Some(updated.join(", "))
+    };
+    hub.events()
+        .update(
+            evt.clone(),
+            &cfg.calendar_main_id,
+            &evt.id.as_ref().cloned().unwrap(),
+        )
+        .doit()
+        .await?;
+    sync_events(cfg, &[]).await?;
+    Ok(true)
+}
+
 fn main_event(evt: &FestivalEvent) -> Event {
     let start = google_calendar3::api::EventDateTime {
         date: Some(evt.date.clone()),
-        date_time: Some(
-            evt.date
-                .and_time(evt.start)
-                .and_local_timezone(chrono::Utc)
-                .unwrap(),
-        ),
-        time_zone: Some(chrono::Utc.to_string()),
+        date_time: Some(festival_instant(evt.date.and_time(evt.start))),
+        time_zone: Some(TIME_ZONE.name().to_string()),
     };
     let end = google_calendar3::api::EventDateTime {
         date: Some(evt.date),
-        date_time: Some(
-            evt.date
-                .and_time(evt.end)
-                .and_local_timezone(chrono::Utc)
-                .unwrap(),
-        ),
-        time_zone: Some(chrono::Utc.to_string()),
+        date_time: Some(festival_instant(evt.date.and_time(evt.end))),
+        time_zone: Some(TIME_ZONE.name().to_string()),
     };
     let mut event = Event::default();
     event.color_id = Some(evt.screen_colour.to_string());
@@ -427,9 +514,24 @@ fn main_event(evt: &FestivalEvent) -> Event {
 
 impl From<Event> for FestivalEvent {
     fn from(value: Event) -> Self {
-        let date = value.start.clone().unwrap().date_time.unwrap().date_naive();
-        let start = value.start.clone().unwrap().date_time.unwrap().time();
-        let end = value.end.clone().unwrap().date_time.unwrap().time();
+        // Back to the times as GFT gives them
+        let start_time = value
+            .start
+            .clone()
+            .unwrap()
+            .date_time
+            .unwrap()
+            .with_timezone(&TIME_ZONE);
+        let date = start_time.date_naive();
+        let start = start_time.time();
+        let end = value
+            .end
+            .clone()
+            .unwrap()
+            .date_time
+            .unwrap()
+            .with_timezone(&TIME_ZONE)
+            .time();
         let movie_id = 0;
         let screening_id = 0;
         let title = value.summary.clone().unwrap();
@@ -442,6 +544,7 @@ impl From<Event> for FestivalEvent {
         let screen_colour = 0;
         let attendees = vec![];
         let synopsis = "".to_owned();
+        let synopsis_html = "".to_owned();
         let starring = vec![];
         let genres = vec![];
         let director = "".to_owned();
@@ -464,12 +567,88 @@ impl From<Event> for FestivalEvent {
             screen_colour,
             attendees,
             synopsis,
+            synopsis_html,
             starring,
             genres,
             director,
             rating,
             rating_reasons,
             poster,
+            ..Default::default()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attendees() {
+        assert_eq!(vec!["Neil", "Marion"], attendees("Neil, Marion\n"));
+        assert!(attendees("").is_empty());
+    }
+
+    #[test]
+    fn test_main_event_running_time() {
+        let evt = FestivalEvent {
+            date: chrono::NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            start: chrono::NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            title: "Hamnet".to_string(),
+            screen: "GFT 1".to_string(),
+            ..Default::default()
+        };
+        let before = main_event_from(&evt);
+        assert_eq!(Some("GFT 1".to_string()), before.location);
+
+        let evt = FestivalEvent {
+            extra_minutes: 30,
+            ..evt
+        };
+        let after = main_event_from(&evt);
+        assert_eq!(
+            Some("GFT 1 (120 min + 30 min after)".to_string()),
+            after.location
+        );
+        // Events already on the calendar pick the running time up on the next upload
+        assert!(!main_events_match(&after, &before));
+    }
+
+    #[test]
+    fn test_summer_time() {
+        // The clocks have gone forward by the last Sunday in March
+        let evt = FestivalEvent {
+            date: chrono::NaiveDate::from_ymd_opt(2026, 3, 30).unwrap(),
+            start: chrono::NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            title: "Hamnet".to_string(),
+            ..Default::default()
+        };
+        let cal = main_event_from(&evt);
+        let start = cal.start.clone().unwrap();
+        assert_eq!(
+            Some("2026-03-30T17:00:00Z".parse().unwrap()),
+            start.date_time
+        );
+        assert_eq!(Some("Europe/London"), start.time_zone.as_deref());
+        // The ics feed gives the same time in the same zone
+        let ics = crate::ics::calendar("GFF", &[&evt]);
+        assert!(ics.contains("DTSTART;TZID=Europe/London:20260330T180000\r\n"));
+        // Reading the event back gives GFT's times again
+        let back = FestivalEvent::from(cal);
+        assert_eq!((evt.date, evt.start), (back.date, back.start));
+    }
+
+    #[test]
+    fn test_toggle_attendee() {
+        let current = vec!["Neil".to_string(), "Marion".to_string()];
+        assert_eq!(vec!["Marion"], toggle_attendee(&current, "Neil", false));
+        assert_eq!(current, toggle_attendee(&current, "Neil", true));
+        assert_eq!(current, toggle_attendee(&current, "Pam", false));
+        assert_eq!(
+            vec!["Neil", "Marion", "Pam"],
+            toggle_attendee(&current, "Pam", true)
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::config::{Config, ConfigError};
+use crate::layers::parse;
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Formatter;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Something in the config file that will go wrong later, though it parsed.
+#[derive(Error, Debug, PartialEq)]
+pub enum ConfigProblem {
+    // Screenings at the id would all go to whichever screen is found first
+    #[error("Screens {1} all have id {0}")]
+    DuplicateScreenId(u32, String),
+    // A badge can only be one strand or screening attribute
+    #[error("Strands and badges {1} all have id {0}")]
+    DuplicateBadgeId(u32, String),
+    // Colours go into typst and css, with transparency added on the end
+    #[error("Strand {0} has colour {1}, which isn't six hex digits such as a30053")]
+    BadStrandColour(String, String),
+    // Google Calendar event colours are numbered 1 to 11
+    #[error("Screen {0} has colour {1}, which isn't a Google Calendar colour from 1 to 11")]
+    BadScreenColour(String, u32),
+    // The later entry silently replaces the earlier one
+    #[error("Initials {0} are used more than once in names")]
+    DuplicateInitials(String),
+    // The initials for the name depend on which is found first
+    #[error("{0} is in names under {1}")]
+    DuplicateName(String, String),
+    // The value is read as empty
+    #[error("{0} is set from environment variable {1}, which is unset or empty")]
+    UnsetEnv(String, String),
+}
+
+// The keys of an object in the order given, duplicates included
+struct Keys(Vec<String>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = Keys;
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                write!(f, "an object")
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Keys, A::Error> {
+                let mut keys = vec![];
+                while let Some((key, _)) = map.next_entry::<String, serde::de::IgnoredAny>()? {
+                    keys.push(key);
+                }
+                Ok(Keys(keys))
+            }
+        }
+        deserializer.deserialize_map(KeysVisitor)
+    }
+}
+
+// Just the names, as a map would drop the duplicates
+#[derive(Deserialize)]
+struct RawNames {
+    #[serde(default)]
+    names: Option<Keys>,
+}
+
+fn duplicated<'a>(items: impl Iterator<Item = (u32, &'a String)>) -> Vec<(u32, String)> {
+    let mut by_id: BTreeMap<u32, Vec<&String>> = BTreeMap::new();
+    // 0 is what Unknown and None get when they aren't configured
+    for (id, name) in items.filter(|(id, _)| *id != 0) {
+        by_id.entry(id).or_default().push(name);
+    }
+    by_id
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|(id, mut names)| {
+            names.sort();
+            (
+                id,
+                names
+                    .iter()
+                    .map(|n| n.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        })
+        .collect()
+}
+
+fn is_hex_colour(colour: &str) -> bool {
+    colour.len() == 6 && colour.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Every "!env VAR" in the raw config, with the path to it
+fn env_references(path: &str, value: &Value, found: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(s) => {
+            if let Some(var) = s.strip_prefix("!env ") {
+                found.push((path.to_string(), var.trim().to_string()));
+            }
+        }
+        Value::Object(map) => {
+            for (k, v) in map {
+                let path = if path.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", path, k)
+                };
+                env_references(&path, v, found);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                env_references(&format!("{}[{}]", path, i), v, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Everything wrong with the config, given the text of the config file it was read from.
+pub fn check(cfg: &Config, file: &str, raw: &str) -> Vec<ConfigProblem> {
+    let mut problems = duplicated(cfg.screens.iter().map(|(k, v)| (v.id, k)))
+        .into_iter()
+        .map(|(id, names)| ConfigProblem::DuplicateScreenId(id, names))
+        .collect::<Vec<_>>();
+    let badges = cfg
+        .strands
+        .iter()
+        .map(|(k, v)| (v.id, k))
+        .chain(cfg.badges.iter().map(|(k, v)| (v.id, k)));
+    problems.extend(
+        duplicated(badges)
+            .into_iter()
+            .map(|(id, names)| ConfigProblem::DuplicateBadgeId(id, names)),
+    );
+
+    let mut strands = cfg.strands.iter().collect::<Vec<_>>();
+    strands.sort_by(|a, b| a.0.cmp(b.0));
+    problems.extend(
+        strands
+            .into_iter()
+            .filter(|(_, s)| !is_hex_colour(&s.colour))
+            .map(|(k, s)| ConfigProblem::BadStrandColour(k.clone(), s.colour.clone())),
+    );
+    let mut screens = cfg.screens.iter().collect::<Vec<_>>();
+    screens.sort_by(|a, b| a.0.cmp(b.0));
+    problems.extend(
+        screens
+            .into_iter()
+            .filter(|(_, s)| !(1..=11).contains(&s.colour))
+            .map(|(k, s)| ConfigProblem::BadScreenColour(k.clone(), s.colour)),
+    );
+
+    // toml won't parse with the same key twice anyway
+    if let Ok(RawNames { names: Some(keys) }) = serde_json::from_str::<RawNames>(raw) {
+        let mut seen = vec![];
+        for key in keys.0 {
+            if seen.contains(&key) {
+                problems.push(ConfigProblem::DuplicateInitials(key));
+            } else {
+                seen.push(key);
+            }
+        }
+    }
+    let mut by_name: BTreeMap<&String, Vec<&String>> = BTreeMap::new();
+    for (initials, name) in &cfg.names {
+        by_name.entry(name).or_default().push(initials);
+    }
+    for (name, mut initials) in by_name.into_iter().filter(|(_, i)| i.len() > 1) {
+        initials.sort();
+        let initials = initials
+            .iter()
+            .map(|i| i.as_str())
+            .collect::<Vec<_>>()
+            .join(" and ");
+        problems.push(ConfigProblem::DuplicateName(name.clone(), initials));
+    }
+
+    if let Ok(value) = parse(Path::new(file), raw) {
+        let mut found = vec![];
+        env_references("", &value, &mut found);
+        problems.extend(
+            found
+                .into_iter()
+                .filter(|(_, var)| std::env::var(var).unwrap_or_default().is_empty())
+                .map(|(path, var)| ConfigProblem::UnsetEnv(path, var)),
+        );
+    }
+    problems
+}
+
+/// Check the config file as it is on disk.
+pub fn check_file(cfg: &Config) -> Result<Vec<ConfigProblem>, String> {
+    let file = cfg.config_file();
+    if file.is_empty() {
+        return Err(ConfigError::MissingConfig(cfg.config_directory()).to_string());
+    }
+    let raw = fs::read_to_string(&file).map_err(|_| format!("Unable to read {}", file))?;
+    Ok(check(cfg, &file, &raw))
+}
+
+/// A JSON Schema for config.json or config.toml, for editors to check and complete it with.
+pub fn schema() -> String {
+    let schema = schemars::schema_for!(Config);
+    serde_json::to_string_pretty(&schema).unwrap_or_default() + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: &str = r##"{
+        "calendar_main_id": "!env GFFD_TEST_UNSET_CALENDAR",
+        "calendar_filter_id": "",
+        "calendar_auth_file": "google_auth.json",
+        "cookie": "",
+        "server_options": {"port": 3020, "callback_url": "", "feed_tokens": {"Neil": "!env GFFD_TEST_UNSET_TOKEN"}},
+        "screens": {
+            "GFT 1": {"id": 171, "colour": 3},
+            "GFT 3": {"id": 171, "colour": 12},
+            "Odeon 10": {"id": 469, "colour": 7}
+        },
+        "strands": {
+            "Gala": {"id": 858, "colour": "f2c170", "priority": 0},
+            "FrightFest": {"id": 857, "colour": "#946c0c", "priority": 4},
+            "Take 2": {"id": 546, "colour": "f1001C", "priority": 11}
+        },
+        "badges": {"Captioned": {"id": 546, "symbol": "CC"}},
+        "names": {"N": "Neil", "M": "Marion", "N": "Nora", "Ma": "Marion"}
+    }"##;
+
+    #[test]
+    fn test_check() {
+        let cfg: Config = serde_json::from_str(RAW).unwrap();
+        let problems = check(&cfg, "config.json", RAW);
+        assert_eq!(
+            vec![
+                ConfigProblem::DuplicateScreenId(171, "GFT 1, GFT 3".to_string()),
+                ConfigProblem::DuplicateBadgeId(546, "Captioned, Take 2".to_string()),
+                ConfigProblem::BadStrandColour("FrightFest".to_string(), "#946c0c".to_string()),
+                ConfigProblem::BadScreenColour("GFT 3".to_string(), 12),
+                ConfigProblem::DuplicateInitials("N".to_string()),
+                ConfigProblem::DuplicateName("Marion".to_string(), "M and Ma".to_string()),
+                ConfigProblem::UnsetEnv(
+                    "calendar_main_id".to_string(),
+                    "GFFD_TEST_UNSET_CALENDAR".to_string()
+                ),
+                ConfigProblem::UnsetEnv(
+                    "server_options.feed_tokens.Neil".to_string(),
+                    "GFFD_TEST_UNSET_TOKEN".to_string()
+                ),
+            ],
+            problems
+        );
+        assert_eq!(
+            "Screen GFT 3 has colour 12, which isn't a Google Calendar colour from 1 to 11",
+            problems[3].to_string()
+        );
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema: Value = serde_json::from_str(&schema()).unwrap();
+        let properties = &schema["properties"];
+        assert!(properties["screens"].is_object());
+        assert!(properties["badges"].is_object());
+        // Set from the command line, not the file
+        assert!(properties["directory"].is_null());
+        // Anything can be left to the defaults, but not half a strand
+        assert!(schema["required"].is_null());
+        assert!(schema["$defs"]["StrandConfig"]["required"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("colour")));
+    }
+}
@@ -1,8 +1,13 @@
-use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
+use crate::layers::{describe, find_file, parse, Layers, Source};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Debug, Formatter};
 use std::fs;
 use std::path::Path;
+use summary::ScreeningBadge;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -10,64 +15,77 @@ pub enum ConfigError {
     // No config directory found
     #[error("No config directory {0}")]
     NoConfigDir(String),
-    // Permission error reading config.json
+    // Permission error reading a config file
     #[error("Config directory permission error for {0}")]
     PermissionError(String),
-    // unreadable config.json
+    // unreadable config file
     #[error("Unable to read {0}")]
     ReadError(String),
-    // Missing config.json
-    #[error("No config.json in {0}")]
+    // No config.json or config.toml where one is needed
+    #[error("No config.json or config.toml in {0}")]
     MissingConfig(String),
+    // Both config.json and config.toml
+    #[error("Only one config file can be used, not {0}")]
+    AmbiguousConfig(String),
     // Missing state_directory
     #[error("state_directory is set to {0} but is not avaiable")]
     MissingStateDir(String),
-    // Invalid json
+    // Invalid json or toml, or a value of the wrong type
     #[error("{0} does not contain a valid config - {1}")]
     InvalidConfig(String, String),
 }
 
-fn deserialize_env_string<'de, D>(deserializer: D) -> Result<String, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let buf = String::deserialize(deserializer)?;
-    if let Some(var_name) = buf.strip_prefix("!env ") {
-        let val = std::env::var(var_name).unwrap_or("".to_string());
-        if val.is_empty() {
-            println!("Environment variable {} is unset or empty", &buf[5..]);
-        }
-        Ok(val)
-    } else {
-        Ok(buf)
-    }
+// Settings for every directory on the machine, under the directory's own config
+const SYSTEM_DIRECTORY: &str = "/etc/gffd";
+// Where to look instead, mainly for trying out a system config
+const SYSTEM_DIRECTORY_VAR: &str = "GFFD_SYSTEM_DIRECTORY";
+
+/// A setting given on the command line, `path` being the field it sets, such as
+/// server_options.port.
+pub struct CliValue {
+    pub path: &'static str,
+    pub flag: &'static str,
+    pub value: Value,
 }
 
-#[derive(Serialize, Deserialize)]
+// Anything missing from every layer is the default, so no field has to be in the file
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct Config {
-    #[serde(deserialize_with = "deserialize_env_string")]
     pub calendar_main_id: String,
-    #[serde(deserialize_with = "deserialize_env_string")]
     pub calendar_filter_id: String,
-    #[serde(deserialize_with = "deserialize_env_string")]
+    // Relative to the config directory
     pub calendar_auth_file: String,
-    #[serde(deserialize_with = "deserialize_env_string")]
     pub cookie: String,
     #[serde(default)]
     pub state_directory: String,
+    // Where gffd publish writes versioned pdfs and their manifest
+    #[serde(default)]
+    pub publish_directory: String,
     pub server_options: ServerConfig,
     pub screens: HashMap<String, ScreenConfig>,
     pub strands: HashMap<String, StrandConfig>,
+    // Showing badges for screening attributes such as captioned or 35mm, by name
+    #[serde(default)]
+    pub badges: HashMap<String, BadgeConfig>,
     pub names: HashMap<String, String>,
+    // Named groups of people, by initials or name, each getting a filtered summary
+    #[serde(default)]
+    pub groups: HashMap<String, Vec<String>>,
     #[serde(skip)]
     directory: String,
     #[serde(skip)]
     debug: bool,
     #[serde(skip)]
     live: bool,
+    // The config.json or config.toml read, and where every value came from
+    #[serde(skip)]
+    file: String,
+    #[serde(skip)]
+    sources: BTreeMap<Vec<String>, Source>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StrandConfig {
     pub id: u32,
     pub colour: String,
@@ -84,7 +102,17 @@ impl Default for StrandConfig {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BadgeConfig {
+    pub id: u32,
+    // Short enough to go on a showing line, such as CC or AD
+    pub symbol: String,
+    // Time to allow after the film, such as for a Q&A
+    #[serde(default)]
+    pub extra_minutes: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScreenConfig {
     pub id: u32,
     pub colour: u32,
@@ -96,10 +124,15 @@ impl Default for ScreenConfig {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct ServerConfig {
     pub port: u16,
     pub callback_url: String,
+    // Secret token per person, required in the url of their ics feeds and to mark
+    // their attendance
+    #[serde(default)]
+    pub feed_tokens: HashMap<String, String>,
 }
 
 impl Debug for Config {
@@ -127,14 +160,19 @@ impl Default for Config {
             calendar_filter_id: "".to_string(),
             calendar_auth_file: "google_auth.json".to_string(),
             state_directory: "".to_string(),
+            publish_directory: "".to_string(),
             server_options: ServerConfig::default(),
             screens: HashMap::default(),
             strands: HashMap::default(),
+            badges: HashMap::default(),
             directory: ".".to_string(),
             cookie: "".to_string(),
             names: HashMap::default(),
+            groups: HashMap::default(),
             debug: false,
             live: false,
+            file: "".to_string(),
+            sources: BTreeMap::new(),
         }
     }
 }
@@ -143,19 +181,12 @@ impl Default for ServerConfig {
         Self {
             port: 3020,
             callback_url: "https://gff.darach.org.uk".to_string(),
+            feed_tokens: HashMap::default(),
         }
     }
 }
 
 impl Config {
-    pub fn set_auth_file(&mut self, file: &str) {
-        self.calendar_auth_file = file.to_string();
-        if Path::new(file).is_relative()
-            && let Some(abspath) = Path::new(&self.directory).join(Path::new(file)).to_str()
-        {
-            self.calendar_auth_file = abspath.to_owned();
-        }
-    }
     pub fn set_debug(&mut self) {
         self.debug = true;
     }
@@ -168,57 +199,136 @@ impl Config {
     pub fn is_live(&self) -> bool {
         self.live
     }
-    pub fn read_config_file(directory: &String) -> Result<Self, ConfigError> {
+    /// The directory's own config file, empty if it doesn't have one.
+    pub fn config_file(&self) -> String {
+        self.file.clone()
+    }
+    pub fn config_directory(&self) -> String {
+        self.directory.clone()
+    }
+    /// Every value with where it came from.
+    pub fn describe(&self) -> String {
+        let value = serde_json::to_value(self).unwrap_or_default();
+        describe(&self.sources, &value)
+    }
+    fn read_layer(file: &String) -> Result<Value, ConfigError> {
+        let text = fs::read_to_string(file).map_err(|e| match e.kind() {
+            std::io::ErrorKind::PermissionDenied => ConfigError::PermissionError(file.clone()),
+            _ => ConfigError::ReadError(file.clone()),
+        })?;
+        parse(Path::new(file), &text).map_err(|e| ConfigError::InvalidConfig(file.clone(), e))
+    }
+    /// The config for `directory`, built up from the defaults, then the system file,
+    /// then the directory's config.json or config.toml, then any GFFD_ environment
+    /// variables and finally the command line.  Either file can be left out.  "!env VAR"
+    /// anywhere is replaced by the variable.
+    pub fn read_config_file(directory: &String, cli: &[CliValue]) -> Result<Self, ConfigError> {
         if let Ok(true) = fs::exists(directory) {
         } else {
             return Err(ConfigError::NoConfigDir(directory.clone()));
         }
-        let cfg_file = format!("{}/config.json", directory);
-        match fs::exists(&cfg_file) {
-            Ok(true) => {
-                let bytes =
-                    fs::read(&cfg_file).map_err(|_| ConfigError::ReadError(cfg_file.clone()))?;
-                let mut cfg: Config = serde_json::from_slice(&bytes[..])
-                    .map_err(|e| ConfigError::InvalidConfig(cfg_file.clone(), format!("{}", e)))?;
-                let dir = fs::canonicalize(directory)
-                    .map_err(|_| ConfigError::NoConfigDir(directory.clone()))?;
-                cfg.directory = dir
-                    .to_str()
-                    .ok_or(ConfigError::NoConfigDir(directory.clone()))?
-                    .to_owned();
-                if !cfg.screens.contains_key("Unknown") {
-                    cfg.screens
-                        .insert("Unknown".to_string(), ScreenConfig::default());
-                }
-                if !cfg.strands.contains_key("None") {
-                    cfg.strands
-                        .insert("None".to_string(), StrandConfig::default());
-                }
-                if cfg.state_directory.is_empty() {
-                    cfg.state_directory = cfg.directory.clone();
-                }
-
-                match fs::exists(&cfg.state_directory) {
-                    Ok(true) => {}
-                    _ => {
-                        return Err(ConfigError::MissingStateDir(cfg.state_directory.clone()));
-                    }
-                }
-                Ok(cfg)
-            }
-            Ok(false) => {
-                Err(ConfigError::MissingConfig(directory.clone()))
-                /*
-                let default = Config::default();
-                fs::write(&cfg_file, serde_json::to_string_pretty(&default)?)?;
-                Ok(default)
-                */
-            }
-            Err(_) => {
-                println!("Unable to access cfg_file.  Check permissions");
-                Err(ConfigError::PermissionError(cfg_file.clone()))
+        let cfg_file = find_file(Path::new(directory)).map_err(ConfigError::AmbiguousConfig)?;
+        let defaults = serde_json::to_value(Config::default())
+            .map_err(|e| ConfigError::InvalidConfig("defaults".to_string(), format!("{}", e)))?;
+        let mut layers = Layers::new(defaults);
+
+        let system = std::env::var(SYSTEM_DIRECTORY_VAR).unwrap_or(SYSTEM_DIRECTORY.to_string());
+        if let Some(file) = find_file(Path::new(&system)).map_err(ConfigError::AmbiguousConfig)? {
+            layers.overlay(&Self::read_layer(&file)?, &Source::System(file));
+        }
+        if let Some(file) = &cfg_file {
+            layers.overlay(&Self::read_layer(file)?, &Source::File(file.clone()));
+        }
+        layers.overlay_env(std::env::vars());
+        for c in cli {
+            let path = c.path.split('.').map(|p| p.to_string()).collect::<Vec<_>>();
+            layers.set(&path, c.value.clone(), Source::Cli(c.flag.to_string()));
+        }
+        layers.substitute_env();
+
+        // Named by the field that is wrong and the layer it came from
+        let mut cfg: Config = serde_path_to_error::deserialize(&layers.value).map_err(|e| {
+            let path = e
+                .path()
+                .iter()
+                .filter_map(|segment| match segment {
+                    Segment::Map { key } => Some(key.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let from = match layers.source_of(&path) {
+                Some(source) => format!("{} from {}", e.path(), source),
+                None => format!("{} in the merged config", e.path()),
+            };
+            ConfigError::InvalidConfig(from, e.into_inner().to_string())
+        })?;
+        cfg.file = cfg_file.unwrap_or_default();
+        cfg.sources = layers.sources;
+        let dir =
+            fs::canonicalize(directory).map_err(|_| ConfigError::NoConfigDir(directory.clone()))?;
+        cfg.directory = dir
+            .to_str()
+            .ok_or(ConfigError::NoConfigDir(directory.clone()))?
+            .to_owned();
+        // Relative to the config directory, whichever layer it came from
+        if Path::new(&cfg.calendar_auth_file).is_relative() {
+            cfg.calendar_auth_file = dir
+                .join(&cfg.calendar_auth_file)
+                .to_string_lossy()
+                .to_string();
+        }
+        if !cfg.screens.contains_key("Unknown") {
+            cfg.screens
+                .insert("Unknown".to_string(), ScreenConfig::default());
+        }
+        if !cfg.strands.contains_key("None") {
+            cfg.strands
+                .insert("None".to_string(), StrandConfig::default());
+        }
+        if cfg.state_directory.is_empty() {
+            cfg.state_directory = cfg.directory.clone();
+        }
+
+        match fs::exists(&cfg.state_directory) {
+            Ok(true) => {}
+            _ => {
+                return Err(ConfigError::MissingStateDir(cfg.state_directory.clone()));
             }
         }
+        Ok(cfg)
+    }
+
+    /// The short form used for a person on the filter calendar and in the summaries.
+    pub fn initials_of(&self, name: &str) -> String {
+        let found = self.names.iter().find(|(_k, v)| *v == name);
+        match found {
+            Some((i, _)) => i.clone(),
+            None => name.chars().take(1).collect(),
+        }
+    }
+
+    /// Everyone in names under their own name, plus the groups, with members as
+    /// initials.  A group with the same name as a person replaces them.
+    pub fn summary_groups(&self) -> BTreeMap<String, Vec<String>> {
+        let mut groups = self
+            .names
+            .iter()
+            .map(|(i, name)| (name.clone(), vec![i.clone()]))
+            .collect::<BTreeMap<_, _>>();
+        for (title, members) in &self.groups {
+            let initials = members
+                .iter()
+                .map(|m| {
+                    if self.names.contains_key(m) {
+                        m.clone()
+                    } else {
+                        self.initials_of(m)
+                    }
+                })
+                .collect();
+            groups.insert(title.clone(), initials);
+        }
+        groups
     }
 
     pub fn screen_from_id(&self, id: u32) -> (String, ScreenConfig) {
@@ -229,15 +339,128 @@ impl Config {
         }
     }
 
-    pub fn strand_from_badges(&self, badges: Vec<u32>) -> (String, StrandConfig) {
-        let found = self
+    /// Every configured strand among the badges, the main one, with the lowest priority,
+    /// first.  Strands with the same priority are taken in name order.
+    pub fn strands_from_badges(&self, badges: &[u32]) -> Vec<(String, StrandConfig)> {
+        let mut found = self
             .strands
             .iter()
             .filter(|(_k, v)| badges.contains(&v.id))
-            .min_by(|a, b| a.1.priority.cmp(&b.1.priority));
-        match found {
-            Some((s, c)) => (s.clone(), c.clone()),
-            None => ("".to_string(), StrandConfig::default()),
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| (a.1.priority, &a.0).cmp(&(b.1.priority, &b.0)));
+        found
+    }
+
+    /// The configured screening attributes among the badges, in name order.
+    pub fn attributes_from_badges(&self, badges: &[u32]) -> Vec<ScreeningBadge> {
+        let mut found = self
+            .badges
+            .iter()
+            .filter(|(_k, v)| badges.contains(&v.id))
+            .map(|(k, v)| ScreeningBadge {
+                name: k.clone(),
+                symbol: v.symbol.clone(),
+            })
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        found
+    }
+
+    /// The extra time allowed for the badges, added up if there are several.
+    pub fn extra_minutes_from_badges(&self, badges: &[u32]) -> u32 {
+        self.badges
+            .values()
+            .filter(|v| badges.contains(&v.id))
+            .map(|v| v.extra_minutes)
+            .sum()
+    }
+
+    pub fn strand_from_badges(&self, badges: Vec<u32>) -> (String, StrandConfig) {
+        self.strands_from_badges(&badges)
+            .into_iter()
+            .next()
+            .unwrap_or(("".to_string(), StrandConfig::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_dir(name: &str, config: Option<&str>) -> String {
+        let dir = std::env::temp_dir().join(format!("gffd-config-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        if let Some(config) = config {
+            fs::write(dir.join("config.json"), config).unwrap();
         }
+        dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_auth_file() {
+        let dir = config_dir(
+            "auth",
+            Some(r#"{"calendar_auth_file": "auth/google.json"}"#),
+        );
+        let cfg = Config::read_config_file(&dir, &[]).unwrap();
+        assert_eq!(
+            Path::new(&cfg.directory).join("auth/google.json"),
+            Path::new(&cfg.calendar_auth_file)
+        );
+        let cli = [CliValue {
+            path: "calendar_auth_file",
+            flag: "--auth",
+            value: "other.json".into(),
+        }];
+        let cfg = Config::read_config_file(&dir, &cli).unwrap();
+        assert_eq!(
+            Path::new(&cfg.directory).join("other.json"),
+            Path::new(&cfg.calendar_auth_file)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_config_file() {
+        let dir = config_dir("none", None);
+        let cli = [CliValue {
+            path: "server_options.port",
+            flag: "--port",
+            value: 3021.into(),
+        }];
+        let cfg = Config::read_config_file(&dir, &cli).unwrap();
+        assert_eq!("", cfg.config_file());
+        assert_eq!(3021, cfg.server_options.port);
+        assert!(cfg.screens.contains_key("Unknown"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let dir = config_dir("invalid", Some(r#"{"names": {"N": 3}}"#));
+        let Err(e) = Config::read_config_file(&dir, &[]) else {
+            panic!("names.N should be text");
+        };
+        let message = e.to_string();
+        assert!(message.starts_with("names.N from file "), "{}", message);
+        assert!(
+            message.contains("config.json does not contain"),
+            "{}",
+            message
+        );
+        let cli = [CliValue {
+            path: "server_options.port",
+            flag: "--port",
+            value: "abc".into(),
+        }];
+        fs::write(Path::new(&dir).join("config.json"), "{}").unwrap();
+        let Err(e) = Config::read_config_file(&dir, &cli) else {
+            panic!("server_options.port should be a number");
+        };
+        assert!(e
+            .to_string()
+            .starts_with("server_options.port from command line --port does not contain"));
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,356 @@
+use crate::config::{Config, ScreenConfig};
+use crate::films::{deserialize_text, fetch_from_gft, FestivalEvent};
+use crate::layers::{parse, write_file};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DiscoverError {
+    // GFT couldn't be reached, or answered with something else
+    #[error("Unable to fetch from GFT - {0}")]
+    FetchError(String),
+    // No config file to merge into
+    #[error("No config.json or config.toml in {0}")]
+    MissingConfig(String),
+    // Unreadable config file
+    #[error("Unable to read {0}")]
+    ReadError(String),
+    // The config file isn't an object with a screens object
+    #[error("{0} does not contain a valid config - {1}")]
+    InvalidConfig(String, String),
+    // Error writing the merged config file
+    #[error("Error writing {0} - {1}")]
+    WriteError(String, String),
+}
+
+// Every venue with its screens, and every badge a showing can have
+const QUERY: &str =
+    r#"{"query": "query { venues { id name screens { id name } } showingBadges { id name } }"}"#;
+
+#[derive(Deserialize, Debug)]
+struct Response {
+    data: Catalogue,
+}
+
+/// The screens and showing badges GFT knows about.
+#[derive(Deserialize, Debug, Default)]
+pub struct Catalogue {
+    #[serde(default)]
+    venues: Vec<Venue>,
+    #[serde(default, rename = "showingBadges")]
+    badges: Vec<Named>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Venue {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    screens: Vec<Named>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Named {
+    #[serde(deserialize_with = "deserialize_text")]
+    id: Option<String>,
+    #[serde(default)]
+    name: String,
+}
+
+impl Named {
+    // GFT's ids are numbers sent as strings
+    fn id(&self) -> Option<u32> {
+        self.id.as_ref().and_then(|id| id.parse().ok())
+    }
+}
+
+/// What `gffd discover` added to the config, the badges GFT has that are neither strands
+/// nor badges yet, and what the screenings use that is still missing from it, with the
+/// films that use each.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    pub screens: Vec<(String, u32)>,
+    pub badges: Vec<(String, u32)>,
+    pub missing_screens: BTreeMap<u32, Vec<String>>,
+    pub missing_badges: BTreeMap<u32, Vec<String>>,
+}
+
+impl Catalogue {
+    pub fn fetch() -> Result<Self, DiscoverError> {
+        Self::parse(&fetch_from_gft(QUERY).map_err(|e| DiscoverError::FetchError(e.to_string()))?)
+    }
+
+    pub fn parse(json: &str) -> Result<Self, DiscoverError> {
+        serde_json::from_str::<Response>(json)
+            .map(|r| r.data)
+            .map_err(|e| DiscoverError::FetchError(format!("unexpected reply - {}", e)))
+    }
+}
+
+// The ids already in a section of the raw config
+fn known_ids(section: &Map<String, Value>) -> HashSet<u32> {
+    section
+        .values()
+        .filter_map(|v| v.get("id")?.as_u64())
+        .map(|id| id as u32)
+        .collect()
+}
+
+// The first of the names not already taken, falling back to one with the id
+fn free_name(section: &Map<String, Value>, names: &[String], id: u32) -> String {
+    names
+        .iter()
+        .filter(|n| !n.trim().is_empty())
+        .find(|n| !section.contains_key(n.as_str()))
+        .cloned()
+        .unwrap_or(format!("{} {}", names[0].trim(), id).trim().to_string())
+}
+
+fn section<'a>(config: &'a mut Value, name: &str) -> Option<&'a mut Map<String, Value>> {
+    config
+        .as_object_mut()?
+        .entry(name)
+        .or_insert(Value::Object(Map::new()))
+        .as_object_mut()
+}
+
+/// Add the screens that aren't in the raw `config` yet, with the default colour.  Badges
+/// that are neither strands nor badges are only listed, as whether one is a strand or a
+/// screening attribute is for a person to choose.  Returns what was found.
+pub fn merge(config: &mut Value, catalogue: &Catalogue) -> Option<Discovery> {
+    let mut discovery = Discovery::default();
+    let mut known = ["strands", "badges"]
+        .iter()
+        .filter_map(|name| config.get(name)?.as_object())
+        .flat_map(known_ids)
+        .collect::<HashSet<_>>();
+    for badge in &catalogue.badges {
+        let Some(id) = badge.id() else { continue };
+        if known.insert(id) {
+            discovery.badges.push((badge.name.clone(), id));
+        }
+    }
+
+    let screens = section(config, "screens")?;
+    let mut known = known_ids(screens);
+    for venue in &catalogue.venues {
+        for screen in &venue.screens {
+            let Some(id) = screen.id() else { continue };
+            if !known.insert(id) {
+                continue;
+            }
+            // A screen name might only make sense with its venue, such as Screen 1
+            let name = free_name(
+                screens,
+                &[
+                    screen.name.clone(),
+                    format!("{} {}", venue.name, screen.name),
+                ],
+                id,
+            );
+            let entry = ScreenConfig {
+                id,
+                ..Default::default()
+            };
+            screens.insert(name.clone(), serde_json::to_value(entry).ok()?);
+            discovery.screens.push((name, id));
+        }
+    }
+    Some(discovery)
+}
+
+impl Discovery {
+    /// Note the screen and badge ids the screenings use that aren't configured, or
+    /// weren't just added or listed.  Screenings cached before the GFT screen id was kept
+    /// have none, so only their badges are checked.
+    pub fn find_missing(&mut self, cfg: &Config, events: &[FestivalEvent]) {
+        let screens = cfg
+            .screens
+            .values()
+            .map(|s| s.id)
+            .chain(self.screens.iter().map(|s| s.1))
+            .collect::<HashSet<_>>();
+        let badges = cfg
+            .strands
+            .values()
+            .map(|s| s.id)
+            .chain(cfg.badges.values().map(|b| b.id))
+            .chain(self.badges.iter().map(|b| b.1))
+            .collect::<HashSet<_>>();
+        let note = |missing: &mut BTreeMap<u32, Vec<String>>, id: u32, title: &String| {
+            let films = missing.entry(id).or_default();
+            if !films.contains(title) {
+                films.push(title.clone());
+            }
+        };
+        for evt in events {
+            if evt.screen_id != 0 && !screens.contains(&evt.screen_id) {
+                note(&mut self.missing_screens, evt.screen_id, &evt.title);
+            }
+            for id in evt.badge_ids.iter().filter(|id| !badges.contains(id)) {
+                note(&mut self.missing_badges, *id, &evt.title);
+            }
+        }
+    }
+}
+
+impl Display for Discovery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (name, id) in &self.screens {
+            writeln!(f, "Added screen {} ({})", name, id)?;
+        }
+        for (name, id) in &self.badges {
+            writeln!(
+                f,
+                "New badge {} ({}), add it to strands or badges",
+                name, id
+            )?;
+        }
+        for (kind, missing) in [
+            ("screen", &self.missing_screens),
+            ("badge", &self.missing_badges),
+        ] {
+            for (id, films) in missing {
+                writeln!(f, "Missing {} {}, used by {}", kind, id, films.join(", "))?;
+            }
+        }
+        if self.screens.is_empty()
+            && self.badges.is_empty()
+            && self.missing_screens.is_empty()
+            && self.missing_badges.is_empty()
+        {
+            writeln!(f, "Nothing new, every screen and badge is configured")?;
+        }
+        Ok(())
+    }
+}
+
+/// Merge what GFT has into the config file, unless this is a dry run, and report what the
+/// screenings use that is still missing.
+pub fn discover(
+    cfg: &Config,
+    catalogue: &Catalogue,
+    events: &[FestivalEvent],
+    dry_run: bool,
+) -> Result<Discovery, DiscoverError> {
+    let file = cfg.config_file();
+    if file.is_empty() {
+        return Err(DiscoverError::MissingConfig(cfg.config_directory()));
+    }
+    let text = fs::read_to_string(&file).map_err(|_| DiscoverError::ReadError(file.clone()))?;
+    // The file as it is, so !env values and anything else not in Config survive the merge
+    let mut config = parse(Path::new(&file), &text)
+        .map_err(|e| DiscoverError::InvalidConfig(file.clone(), e))?;
+    let mut discovery = merge(&mut config, catalogue).ok_or(DiscoverError::InvalidConfig(
+        file.clone(),
+        "screens has to be an object".to_string(),
+    ))?;
+    if !discovery.screens.is_empty() && !dry_run {
+        write_file(Path::new(&file), &config)
+            .map_err(|e| DiscoverError::WriteError(file.clone(), e))?;
+    }
+    discovery.find_missing(cfg, events);
+    Ok(discovery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const REPLY: &str = r#"{"data": {
+        "venues": [
+            {"id": "1", "name": "GFT", "screens": [
+                {"id": "171", "name": "GFT 1"},
+                {"id": "175", "name": "GFT 3"}
+            ]},
+            {"id": "2", "name": "Odeon", "screens": [
+                {"id": "469", "name": "Screen 10"},
+                {"id": "470", "name": "GFT 1"}
+            ]}
+        ],
+        "showingBadges": [
+            {"id": "852", "name": "Official Selection"},
+            {"id": 546, "name": "Captioned"},
+            {"id": "1591", "name": "Truth to Power"},
+            {"id": "x", "name": "Broken"}
+        ]
+    }}"#;
+
+    #[test]
+    fn test_merge() {
+        let catalogue = Catalogue::parse(REPLY).unwrap();
+        let mut config = json!({
+            "cookie": "!env GFT_COOKIE",
+            "screens": {"GFT 1": {"id": 171, "colour": 3}},
+            "strands": {"Official Selection": {"id": 852, "colour": "6596d0", "priority": 9}},
+            "badges": {"Captioned": {"id": 546, "symbol": "CC"}}
+        });
+        let discovery = merge(&mut config, &catalogue).unwrap();
+        assert_eq!(
+            vec![
+                ("GFT 3".to_string(), 175),
+                ("Screen 10".to_string(), 469),
+                ("Odeon GFT 1".to_string(), 470)
+            ],
+            discovery.screens
+        );
+        assert_eq!(vec![("Truth to Power".to_string(), 1591)], discovery.badges);
+        assert_eq!(json!({"id": 175, "colour": 7}), config["screens"]["GFT 3"]);
+        // Left for a person to make a strand or a badge
+        assert!(config["strands"].get("Truth to Power").is_none());
+        assert!(config["badges"].get("Truth to Power").is_none());
+        assert_eq!(json!(3), config["screens"]["GFT 1"]["colour"]);
+        assert_eq!(json!("!env GFT_COOKIE"), config["cookie"]);
+
+        // Again, and there is nothing left to add
+        let again = merge(&mut config, &catalogue).unwrap();
+        assert!(again.screens.is_empty());
+
+        assert!(merge(&mut json!({"screens": []}), &catalogue).is_none());
+    }
+
+    #[test]
+    fn test_missing() {
+        let mut cfg = Config::default();
+        cfg.screens
+            .insert("GFT 1".to_string(), ScreenConfig { id: 171, colour: 3 });
+        let event = |title: &str, screen_id: u32, badge_ids: Vec<u32>| FestivalEvent {
+            title: title.to_string(),
+            screen_id,
+            badge_ids,
+            ..Default::default()
+        };
+        let events = vec![
+            event("Hamnet", 171, vec![852]),
+            event("Fargo", 175, vec![852, 546]),
+            event("Fargo", 175, vec![546]),
+            event("Cached", 0, vec![]),
+        ];
+        let mut discovery = Discovery {
+            badges: vec![("Official Selection".to_string(), 852)],
+            ..Default::default()
+        };
+        discovery.find_missing(&cfg, &events);
+        assert_eq!(
+            vec![175],
+            discovery
+                .missing_screens
+                .keys()
+                .copied()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["Fargo"], discovery.missing_badges[&546]);
+        assert_eq!(
+            "New badge Official Selection (852), add it to strands or badges\n\
+             Missing screen 175, used by Fargo\n\
+             Missing badge 546, used by Fargo\n",
+            discovery.to_string()
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use crate::args::{CsvLayout, ExportFilter};
+use crate::films::FestivalEvent;
+use std::fs;
+use std::io::Write;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    // Error writing the export
+    #[error("Error writing {0} - {1}")]
+    WriteError(String, String),
+    // A column name that isn't in any layout
+    #[error("Unknown column {0}")]
+    UnknownColumn(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Subject,
+    StartDate,
+    StartTime,
+    EndTime,
+    AllDayEvent,
+    Description,
+    Location,
+    Private,
+    Title,
+    Time,
+    Duration,
+    Screen,
+    Synopsis,
+    Starring,
+    Genre,
+    AllGenres,
+    DirectedBy,
+    Rating,
+    RatingReason,
+    Strand,
+    Trailer,
+    Poster,
+    Year,
+    Country,
+    Language,
+    Subtitles,
+    Certificate,
+    Badges,
+}
+
+impl Column {
+    const ALL: [Column; 28] = [
+        Column::Subject,
+        Column::StartDate,
+        Column::StartTime,
+        Column::EndTime,
+        Column::AllDayEvent,
+        Column::Description,
+        Column::Location,
+        Column::Private,
+        Column::Title,
+        Column::Time,
+        Column::Duration,
+        Column::Screen,
+        Column::Synopsis,
+        Column::Starring,
+        Column::Genre,
+        Column::AllGenres,
+        Column::DirectedBy,
+        Column::Rating,
+        Column::RatingReason,
+        Column::Strand,
+        Column::Trailer,
+        Column::Poster,
+        Column::Year,
+        Column::Country,
+        Column::Language,
+        Column::Subtitles,
+        Column::Certificate,
+        Column::Badges,
+    ];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Subject => "Subject",
+            Column::StartDate => "Start Date",
+            Column::StartTime => "Start Time",
+            Column::EndTime => "End Time",
+            Column::AllDayEvent => "All Day Event",
+            Column::Description => "Description",
+            Column::Location => "Location",
+            Column::Private => "Private",
+            Column::Title => "Title",
+            Column::Time => "Time",
+            Column::Duration => "Duration (min)",
+            Column::Screen => "Screen",
+            Column::Synopsis => "Synopsis",
+            Column::Starring => "Starring",
+            Column::Genre => "Genre",
+            Column::AllGenres => "All Genres",
+            Column::DirectedBy => "Directed By",
+            Column::Rating => "Rating",
+            Column::RatingReason => "Rating Reason",
+            Column::Strand => "Strand",
+            Column::Trailer => "Youtube Trailer Id",
+            Column::Poster => "Poster",
+            Column::Year => "Year",
+            Column::Country => "Country",
+            Column::Language => "Language",
+            Column::Subtitles => "Subtitles",
+            Column::Certificate => "Certificate",
+            Column::Badges => "Badges",
+        }
+    }
+
+    pub fn from_header(name: &str) -> Result<Self, ExportError> {
+        Self::ALL
+            .iter()
+            .find(|c| c.header().eq_ignore_ascii_case(name.trim()))
+            .copied()
+            .ok_or(ExportError::UnknownColumn(name.to_string()))
+    }
+
+    fn value(&self, evt: &FestivalEvent) -> String {
+        match self {
+            Column::Subject | Column::Title => evt.title.clone(),
+            Column::StartDate => evt.date.format("%m/%d/%Y").to_string(),
+            Column::StartTime => evt.start.format("%-I:%M %p").to_string(),
+            // Allowing for a Q&A, so the calendar shows the time taken up
+            Column::EndTime => evt.adjusted_end().format("%-I:%M %p").to_string(),
+            Column::AllDayEvent | Column::Private => "False".to_string(),
+            // As the calendar feed describes a screening, without who's going
+            Column::Description => [evt.details(), evt.plain_synopsis()]
+                .into_iter()
+                .filter(|e| !e.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            Column::Location | Column::Screen => evt.screen.clone(),
+            Column::Time => evt
+                .date
+                .and_time(evt.start)
+                .format("%Y/%m/%d %H:%M")
+                .to_string(),
+            Column::Duration => evt.duration().to_string(),
+            Column::Synopsis => evt.plain_synopsis(),
+            Column::Starring => evt.starring.join(", "),
+            Column::Genre => evt.primary_genre(),
+            Column::AllGenres => evt.genres.join(", "),
+            Column::DirectedBy => evt.director.clone(),
+            Column::Rating => evt.rating.clone(),
+            Column::RatingReason => evt.rating_reasons.join(", "),
+            Column::Strand => evt.strand.clone(),
+            Column::Poster if evt.poster.is_empty() => "".to_string(),
+            Column::Poster => format!("https://indy-systems.imgix.net/{}", evt.poster),
+            // The url, as the jq sheet had it
+            Column::Trailer => evt.trailer_url(),
+            Column::Year => evt.year.clone(),
+            Column::Country => evt.country.clone(),
+            Column::Language => evt.language.clone(),
+            Column::Subtitles => evt.subtitles.clone(),
+            Column::Certificate => evt.certificate.clone(),
+            Column::Badges => evt
+                .badges
+                .iter()
+                .map(|b| b.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+impl CsvLayout {
+    pub fn columns(&self) -> Vec<Column> {
+        match self {
+            CsvLayout::Google => vec![
+                Column::Subject,
+                Column::StartDate,
+                Column::StartTime,
+                Column::EndTime,
+                Column::AllDayEvent,
+                Column::Description,
+                Column::Location,
+                Column::Private,
+            ],
+            CsvLayout::Sheet => vec![
+                Column::Title,
+                Column::Time,
+                Column::Duration,
+                Column::Screen,
+                Column::Synopsis,
+                Column::Starring,
+                Column::Genre,
+                Column::AllGenres,
+                Column::DirectedBy,
+                Column::Rating,
+                Column::RatingReason,
+                Column::Strand,
+                Column::Trailer,
+                Column::Poster,
+                Column::Year,
+                Column::Country,
+                Column::Language,
+                Column::Subtitles,
+                Column::Certificate,
+                Column::Badges,
+            ],
+        }
+    }
+}
+
+impl ExportFilter {
+    /// True if the screening passes every filter that was given.  Strands and screens
+    /// are compared ignoring case, and a screening in several strands matches any of them.
+    /// A screening has to have every badge asked for.
+    pub fn matches(&self, evt: &FestivalEvent) -> bool {
+        (self.strands.is_empty() || self.strands.iter().any(|s| evt.in_strand(s)))
+            && (self.screens.is_empty()
+                || self
+                    .screens
+                    .iter()
+                    .any(|s| s.eq_ignore_ascii_case(&evt.screen)))
+            && self.from.is_none_or(|d| evt.date >= d)
+            && self.to.is_none_or(|d| evt.date <= d)
+            && (self.people.is_empty() || self.people.iter().any(|p| evt.attendees.contains(p)))
+            && self.badges.iter().all(|b| evt.has_badge(b))
+    }
+
+    pub fn needs_attendance(&self) -> bool {
+        !self.people.is_empty()
+    }
+}
+
+/// The screenings matching the filter, in date, time and screen order.
+pub fn select<'a>(events: &'a [FestivalEvent], filter: &ExportFilter) -> Vec<&'a FestivalEvent> {
+    let mut selected = events
+        .iter()
+        .filter(|e| filter.matches(e))
+        .collect::<Vec<_>>();
+    selected.sort_by(|a, b| {
+        (a.date, a.start, &a.screen, a.screening_id).cmp(&(
+            b.date,
+            b.start,
+            &b.screen,
+            b.screening_id,
+        ))
+    });
+    selected
+}
+
+/// A header row followed by one row per screening, quoted where needed.
+pub fn csv(events: &[&FestivalEvent], columns: &[Column]) -> Result<Vec<u8>, ExportError> {
+    let to_error = |e: csv::Error| ExportError::WriteError("csv".to_string(), format!("{}", e));
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record(columns.iter().map(|c| c.header()))
+        .map_err(to_error)?;
+    for evt in events {
+        writer
+            .write_record(columns.iter().map(|c| c.value(evt)))
+            .map_err(to_error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| ExportError::WriteError("csv".to_string(), format!("{}", e)))
+}
+
+/// Write to the named file, or to stdout if no file was given.
+pub fn write_output(output: &str, data: &[u8]) -> Result<(), ExportError> {
+    if output.is_empty() {
+        std::io::stdout()
+            .write_all(data)
+            .map_err(|e| ExportError::WriteError("stdout".to_string(), format!("{}", e)))
+    } else {
+        fs::write(output, data)
+            .map_err(|e| ExportError::WriteError(output.to_string(), format!("{}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn event(day: u32, strand: &str, screen: &str, attendees: &[&str]) -> FestivalEvent {
+        FestivalEvent {
+            date: NaiveDate::from_ymd_opt(2026, 2, day).unwrap(),
+            strand: strand.to_string(),
+            screen: screen.to_string(),
+            attendees: attendees.iter().map(|e| e.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_google_csv() {
+        let evt = FestivalEvent {
+            date: NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            start: chrono::NaiveTime::from_hms_opt(13, 10, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(14, 18, 0).unwrap(),
+            title: "I Am \"Martin\" Parr, again".to_string(),
+            screen: "GFT 1".to_string(),
+            ..Default::default()
+        };
+        let out = csv(&[&evt], &CsvLayout::Google.columns()).unwrap();
+        assert_eq!(
+            "Subject,Start Date,Start Time,End Time,All Day Event,Description,Location,Private\n\
+             \"I Am \"\"Martin\"\" Parr, again\",02/25/2026,1:10 PM,2:18 PM,False,,GFT 1,False\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let evt = FestivalEvent {
+            year: "2025".to_string(),
+            country: "UK".to_string(),
+            synopsis: "A photographer at home.".to_string(),
+            ..evt
+        };
+        assert_eq!(
+            "2025, UK\n\nA photographer at home.",
+            Column::Description.value(&evt)
+        );
+    }
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(
+            Column::DirectedBy,
+            Column::from_header("directed by").unwrap()
+        );
+        assert!(Column::from_header("Nonsense").is_err());
+        assert_eq!(20, CsvLayout::Sheet.columns().len());
+    }
+
+    #[test]
+    fn test_movie_columns() {
+        let evt = FestivalEvent {
+            genres: vec!["Drama".to_string(), "Comedy".to_string()],
+            genre: "Comedy".to_string(),
+            trailer_youtube_id: "h2tY82z3xXU".to_string(),
+            year: "2025".to_string(),
+            subtitles: "English subtitles".to_string(),
+            ..Default::default()
+        };
+        let columns = [
+            Column::Genre,
+            Column::Trailer,
+            Column::Year,
+            Column::Subtitles,
+            Column::Country,
+        ];
+        assert_eq!(
+            "Genre,Youtube Trailer Id,Year,Subtitles,Country\n\
+             Comedy,https://www.youtube.com/watch?v=h2tY82z3xXU,2025,English subtitles,\n",
+            String::from_utf8(csv(&[&evt], &columns).unwrap()).unwrap()
+        );
+        let evt = FestivalEvent {
+            genres: vec!["Drama".to_string()],
+            ..Default::default()
+        };
+        assert_eq!("Drama", Column::Genre.value(&evt));
+        assert_eq!("", Column::Trailer.value(&evt));
+    }
+
+    #[test]
+    fn test_filter() {
+        let events = vec![
+            event(25, "Gala", "GFT 1", &["Neil"]),
+            event(26, "FrightFest", "GFT 2", &[]),
+            event(27, "Gala", "Odeon 10", &["Marion"]),
+        ];
+        let filter = ExportFilter {
+            strands: vec!["gala".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(2, select(&events, &filter).len());
+        let filter = ExportFilter {
+            from: NaiveDate::from_ymd_opt(2026, 2, 26),
+            to: NaiveDate::from_ymd_opt(2026, 2, 26),
+            ..Default::default()
+        };
+        assert_eq!("FrightFest", select(&events, &filter)[0].strand);
+        let filter = ExportFilter {
+            people: vec!["Marion".to_string()],
+            screens: vec!["Odeon 10".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 2, 27).unwrap(),
+            select(&events, &filter)[0].date
+        );
+        assert_eq!(3, select(&events, &ExportFilter::default()).len());
+
+        // FrightFest is also in Country Focus
+        let mut events = events;
+        events[1].strands = vec![
+            summary::StrandMarker {
+                name: "FrightFest".to_string(),
+                colour: "946c0c".to_string(),
+            },
+            summary::StrandMarker {
+                name: "Country Focus".to_string(),
+                colour: "00997e".to_string(),
+            },
+        ];
+        let filter = ExportFilter {
+            strands: vec!["country focus".to_string()],
+            ..Default::default()
+        };
+        assert_eq!("FrightFest", select(&events, &filter)[0].strand);
+
+        // Only the gala on the 27th is captioned and audio described
+        let badge = |name: &str, symbol: &str| summary::ScreeningBadge {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+        };
+        events[0].badges = vec![badge("Captioned", "CC")];
+        events[2].badges = vec![badge("Audio Described", "AD"), badge("Captioned", "CC")];
+        let filter = ExportFilter {
+            badges: vec!["cc".to_string(), "audio described".to_string()],
+            ..Default::default()
+        };
+        let selected = select(&events, &filter);
+        assert_eq!(1, selected.len());
+        assert_eq!("Odeon 10", selected[0].screen);
+        assert_eq!(
+            "Audio Described, Captioned",
+            Column::Badges.value(selected[0])
+        );
+    }
+}
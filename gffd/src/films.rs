@@ -5,11 +5,24 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::fs;
-use std::sync::LazyLock;
 use std::thread::sleep;
 use std::time::Duration;
+use summary::{join_known, IntoTypst, RichText, ScreeningBadge, StrandMarker};
 use thiserror::Error;
 
+/// GFT gives screening times as shown at the cinema, so in Glasgow's time zone.
+pub const TIME_ZONE: chrono_tz::Tz = chrono_tz::Europe::London;
+
+/// A screening time from GFT as an instant.  Times skipped when the clocks go forward
+/// are read as GMT.
+pub fn festival_instant(local: NaiveDateTime) -> DateTime<Utc> {
+    TIME_ZONE
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|e| e.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
 #[derive(Error, Debug)]
 pub enum FilmError {
     // Bad date format
@@ -57,7 +70,8 @@ impl Debug for FilmMap {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+// IntoTypst lets a screening be handed to a template as it is
+#[derive(Clone, Default, Serialize, Deserialize, Debug, IntoTypst)]
 pub struct FestivalEvent {
     #[serde(
         deserialize_with = "deserialize_date",
@@ -85,13 +99,43 @@ pub struct FestivalEvent {
     pub screen_id: u32,
     pub screen_colour: u32,
     pub attendees: Vec<String>,
+    // Typst markup, made from synopsis_html
     pub synopsis: String,
+    // As GFT sends it, kept so other formats don't have to go through typst
+    #[serde(default)]
+    pub synopsis_html: String,
     pub starring: Vec<String>,
     pub genres: Vec<String>,
     pub director: String,
     pub rating: String,
     pub rating_reasons: Vec<String>,
     pub poster: String,
+    // The rest of the GFT movie record, missing from screenings cached before it was kept
+    #[serde(default)]
+    pub genre: String,
+    #[serde(default)]
+    pub trailer_youtube_id: String,
+    #[serde(default)]
+    pub year: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub subtitles: String,
+    #[serde(default)]
+    pub certificate: String,
+    // Every badge on the showing, and the known strands among them, main strand first
+    #[serde(default)]
+    pub badge_ids: Vec<u32>,
+    #[serde(default)]
+    pub strands: Vec<StrandMarker>,
+    // The configured screening attributes among the badges, such as captioned
+    #[serde(default)]
+    pub badges: Vec<ScreeningBadge>,
+    // Allowed after the film for the badges, such as a Q&A, `end` being when the film ends
+    #[serde(default)]
+    pub extra_minutes: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -103,6 +147,9 @@ pub struct SummaryEntry {
     pub color: String,
     pub id: String,
     pub day: String,
+    pub secondary: Vec<StrandMarker>,
+    pub badges: Vec<ScreeningBadge>,
+    pub extra_minutes: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -113,6 +160,7 @@ pub struct BrochureEntry {
     pub showings: Vec<Showing>,
     pub duration: u32,
     pub synopsis: String,
+    pub synopsis_html: String,
     pub starring: String,
     pub genres: String,
     #[serde(rename = "directedBy")]
@@ -121,15 +169,24 @@ pub struct BrochureEntry {
     #[serde(rename = "ratingReason")]
     pub rating_reason: String,
     pub strand: String,
+    pub colour: String,
     pub poster: String,
+    pub year: String,
+    pub country: String,
+    pub language: String,
+    pub subtitles: String,
+    pub certificate: String,
+    pub trailer: String,
+    pub secondary: Vec<StrandMarker>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Showing {
-    screen: String,
-    time: String,
-    date: String,
-    datetime: String,
+    pub screen: String,
+    pub time: String,
+    pub date: String,
+    pub datetime: String,
+    pub badges: Vec<ScreeningBadge>,
 }
 
 impl SummaryEntry {
@@ -155,6 +212,9 @@ impl SummaryEntry {
                         id: format!("{}", e.movie_id.clone()),
                         duration: duration.try_into().unwrap(),
                         day,
+                        secondary: e.secondary_strands(),
+                        badges: e.badges.clone(),
+                        extra_minutes: e.extra_minutes,
                     },
                 )
             })
@@ -201,6 +261,7 @@ impl BrochureEntry {
                     time,
                     date,
                     datetime,
+                    badges: e.badges.clone(),
                 }
             })
             .collect::<Vec<_>>();
@@ -211,6 +272,13 @@ impl BrochureEntry {
         if duration < 0 {
             duration += 24 * 60;
         }
+        // The main genre first, as the jq scripts did
+        let genre = movie.primary_genre();
+        let genres = std::iter::once(&genre)
+            .chain(movie.genres.iter().filter(|g| **g != genre))
+            .filter(|g| !g.is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
         Self {
             name: movie.title.clone(),
             id: format!("{}", movie.movie_id),
@@ -218,13 +286,22 @@ impl BrochureEntry {
             showings,
             duration: duration.try_into().unwrap(),
             synopsis: movie.synopsis.clone(),
+            synopsis_html: movie.html_synopsis(),
             starring: movie.starring.join(", "),
-            genres: movie.genres.join(", "),
+            genres: genres.join(", "),
             directed_by: movie.director.clone(),
             rating: movie.rating.clone(),
             rating_reason: movie.rating_reasons.join(", "),
             strand: movie.strand.clone(),
+            colour: movie.strand_colour.clone(),
             poster: format!("posters/{}.jpg", &movie.poster),
+            year: movie.year.clone(),
+            country: movie.country.clone(),
+            language: movie.language.clone(),
+            subtitles: movie.subtitles.clone(),
+            certificate: movie.certificate.clone(),
+            trailer: movie.trailer_url(),
+            secondary: movie.secondary_strands(),
         }
     }
 }
@@ -259,6 +336,20 @@ struct Movie {
     rating: Option<String>,
     #[serde(default, rename = "ratingReason")]
     rating_reason: Option<String>,
+    #[serde(default)]
+    genre: Option<String>,
+    #[serde(default, rename = "trailerYoutubeId")]
+    trailer_youtube_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_text")]
+    year: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    subtitles: Option<String>,
+    #[serde(default)]
+    certificate: Option<String>,
 }
 
 impl FestivalEvent {
@@ -266,27 +357,122 @@ impl FestivalEvent {
         input.split(",").map(|e| e.trim().to_string()).collect()
     }
     fn markup(source: &str) -> Result<String, FilmError> {
-        use regex::Regex;
-        static PATTERNS: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
-            [
-                (r"------*", "----"),
-                (r"<a [^>]+>(?<match>[^<]+)</a>", "($match)"),
-                (r"</?style>", ""),
-                (r"</?font[^>]*>", ""),
-                (r"</?[iI]/?>", "_"),
-                (r"<[bB]>(?<match>[^<]*)</[bB]>", "#strong[$match]"),
-                //(r"<[iI]>(?<match>[^<]*)</[iI]>", "#emph[$match]"),
-                (r"</?[^>]+/?>", ""),
-                (r"\$", r"\$"),
-                (r"\*", r"\*"),
-            ]
+        Ok(RichText::parse(source).to_typst())
+    }
+    /// Running time in minutes, allowing for screenings that finish after midnight.
+    pub fn duration(&self) -> u32 {
+        let mut duration = (self.end - self.start).num_minutes();
+        if duration < 0 {
+            duration += 24 * 60;
+        }
+        duration as u32
+    }
+    /// When the screening is over, allowing for the extra minutes.
+    pub fn adjusted_end(&self) -> NaiveTime {
+        self.end + chrono::TimeDelta::minutes(self.extra_minutes.into())
+    }
+    /// Start and adjusted end, the end being the next day if it is past midnight.
+    pub fn span(&self) -> (NaiveDateTime, NaiveDateTime) {
+        let start = self.date.and_time(self.start);
+        (
+            start,
+            start + chrono::TimeDelta::minutes((self.duration() + self.extra_minutes).into()),
+        )
+    }
+    /// The film's own running time, and any extra minutes allowed after it.
+    pub fn running_time(&self) -> String {
+        if self.extra_minutes == 0 {
+            format!("{} min", self.duration())
+        } else {
+            format!("{} min + {} min after", self.duration(), self.extra_minutes)
+        }
+    }
+    /// True if the two screenings overlap once the extra minutes are allowed for.
+    pub fn clashes_with(&self, other: &Self) -> bool {
+        let (start, end) = self.span();
+        let (other_start, other_end) = other.span();
+        start < other_end && other_start < end
+    }
+    /// The synopsis as plain text.  Screenings cached before the html was kept only
+    /// have the markup, so have it stripped out instead.
+    pub fn plain_synopsis(&self) -> String {
+        if !self.synopsis_html.is_empty() {
+            return RichText::parse(&self.synopsis_html).to_plain();
+        }
+        self.synopsis
+            .replace("#strong[", "")
+            .replace([']', '_'], "")
+            .replace("\\$", "$")
+            .replace("\\*", "*")
+    }
+    /// The synopsis as html, made from the plain text for screenings cached before the
+    /// html was kept.
+    pub fn html_synopsis(&self) -> String {
+        if !self.synopsis_html.is_empty() {
+            return self.synopsis_html.clone();
+        }
+        RichText::from_plain(&self.plain_synopsis()).to_html()
+    }
+    /// The strands other than the main one.
+    pub fn secondary_strands(&self) -> Vec<StrandMarker> {
+        self.strands
             .iter()
-            .map(|(r, m)| (Regex::new(r).unwrap(), *m))
+            .filter(|s| s.name != self.strand)
+            .cloned()
             .collect()
-        });
-        Ok(PATTERNS.iter().fold(source.to_string(), |st, re| {
-            re.0.replace_all(&st, re.1).to_string()
-        }))
+    }
+    /// True if the main or any other strand is `name`, ignoring case.
+    pub fn in_strand(&self, name: &str) -> bool {
+        self.strand.eq_ignore_ascii_case(name)
+            || self
+                .strands
+                .iter()
+                .any(|s| s.name.eq_ignore_ascii_case(name))
+    }
+    /// True if the screening has the badge `name`, by name or symbol ignoring case.
+    pub fn has_badge(&self, name: &str) -> bool {
+        self.badges
+            .iter()
+            .any(|b| b.name.eq_ignore_ascii_case(name) || b.symbol.eq_ignore_ascii_case(name))
+    }
+    /// The main genre, falling back to the first of all of them.
+    pub fn primary_genre(&self) -> String {
+        if self.genre.is_empty() {
+            self.genres.first().cloned().unwrap_or_default()
+        } else {
+            self.genre.clone()
+        }
+    }
+    pub fn trailer_url(&self) -> String {
+        if self.trailer_youtube_id.is_empty() {
+            "".to_string()
+        } else {
+            format!(
+                "https://www.youtube.com/watch?v={}",
+                self.trailer_youtube_id
+            )
+        }
+    }
+    /// Year and country, as far as they're known.
+    pub fn made(&self) -> String {
+        join_known(&[&self.year, &self.country])
+    }
+    /// Language and subtitles, as far as they're known.
+    pub fn spoken(&self) -> String {
+        join_known(&[&self.language, &self.subtitles])
+    }
+    /// Everything known of year, country, language, subtitles and certificate.
+    pub fn details(&self) -> String {
+        join_known(&[&self.made(), &self.spoken(), &self.certificate])
+    }
+    /// Every screening of every film in the id map.
+    pub fn fetch_all(cfg: &Config) -> Result<Vec<Self>, FilmError> {
+        let map = id_map(cfg)?;
+        let mut events = vec![];
+        for id in map.id_to_film.keys() {
+            events.append(&mut Self::fetch_from_gft(cfg, *id)?);
+        }
+        Ok(events)
     }
     pub fn fetch_from_gft(cfg: &Config, movie_id: u32) -> Result<Vec<Self>, FilmError> {
         let cache_file = format!("{}/screenings/{}.json", &cfg.state_directory, movie_id);
@@ -332,7 +518,10 @@ impl FestivalEvent {
                             .unwrap_or("".to_string()),
                     );
                     let screen_id = screening.screen_id.clone().parse().unwrap_or(0);
-                    let (strand_name, strand) = cfg.strand_from_badges(badge_ids);
+                    let strands = cfg.strands_from_badges(&badge_ids);
+                    let badges = cfg.attributes_from_badges(&badge_ids);
+                    let extra_minutes = cfg.extra_minutes_from_badges(&badge_ids);
+                    let (strand_name, strand) = cfg.strand_from_badges(badge_ids.clone());
                     let (screen_name, screen) = cfg.screen_from_id(screen_id);
                     result.push(Self {
                         date,
@@ -346,16 +535,35 @@ impl FestivalEvent {
                         strand_colour: strand.colour,
                         strand_priority: strand.priority,
                         screen: screen_name,
-                        screen_id: screen.id,
+                        // GFT's id, so a screen missing from the config can be found
+                        screen_id,
                         screen_colour: screen.colour,
                         attendees: vec![],
                         synopsis: Self::markup(&movie.synopsis)?,
+                        synopsis_html: movie.synopsis.clone(),
                         starring,
                         genres,
                         director: (movie.directed_by.unwrap_or("".to_string())).clone(),
                         rating: movie.rating.unwrap_or("".to_string()),
                         rating_reasons,
                         poster: movie.poster_image.unwrap_or("".to_string()),
+                        genre: movie.genre.unwrap_or_default(),
+                        trailer_youtube_id: movie.trailer_youtube_id.unwrap_or_default(),
+                        year: movie.year.unwrap_or_default(),
+                        country: movie.country.unwrap_or_default(),
+                        language: movie.language.unwrap_or_default(),
+                        subtitles: movie.subtitles.unwrap_or_default(),
+                        certificate: movie.certificate.unwrap_or_default(),
+                        badge_ids,
+                        strands: strands
+                            .into_iter()
+                            .map(|(name, s)| StrandMarker {
+                                name,
+                                colour: s.colour,
+                            })
+                            .collect(),
+                        badges,
+                        extra_minutes,
                     });
                 }
                 fs::write(
@@ -375,6 +583,18 @@ impl FestivalEvent {
     }
 }
 
+// GFT gives some fields as numbers or strings depending on the film
+pub(crate) fn deserialize_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => None,
+        Some(Value::String(text)) => Some(text),
+        Some(other) => Some(other.to_string()),
+    })
+}
+
 fn serialize_date<S>(date: &NaiveDate, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -390,7 +610,7 @@ where
 }
 
 pub fn fetch_screenings(cfg: &Config, id: u32) -> Result<Vec<Screening>, FilmError> {
-    let graphql = r#"{"query": "query { movie(id: &) { id name posterImage synopsis starring directedBy duration allGenres rating ratingReason showings { id screenId time showingBadgeIds } }}}"}"#.replace("&",&format!("{}",id));
+    let graphql = r#"{"query": "query { movie(id: &) { id name posterImage synopsis starring directedBy duration allGenres rating ratingReason genre trailerYoutubeId year country language subtitles certificate showings { id screenId time showingBadgeIds } }}}"}"#.replace("&",&format!("{}",id));
     let from_gft = fetch_from_gft(&graphql)?;
     if cfg.is_debug() {
         println!("{}", &from_gft);
@@ -529,6 +749,18 @@ pub fn fetch_from_gft(graphql: &str) -> Result<String, FilmError> {
 mod tests {
     use super::*;
     #[test]
+    fn test_festival_instant() {
+        let at = |date: &str| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap();
+        for (local, utc) in [
+            ("2026-02-25 18:00", "2026-02-25 18:00"),
+            ("2026-03-30 18:00", "2026-03-30 17:00"),
+            // Skipped when the clocks went forward
+            ("2026-03-29 01:30", "2026-03-29 01:30"),
+        ] {
+            assert_eq!(at(utc).and_utc(), festival_instant(at(local)));
+        }
+    }
+    #[test]
     fn test_load_ids() {
         let map = load_ids(
             r#"{"data":{"movies":{"data":[{"id":"33606","name":"A Fox Under a Pink Moon"},{"id":"33607","name":"A Place For Her"}],"resultVersion":"3538720778"}}}"#,
@@ -538,15 +770,217 @@ mod tests {
         assert!(false);
     }
     #[test]
+    fn test_strands() {
+        let mut cfg = Config::default();
+        for (name, id, priority) in [
+            ("Official Selection", 852, 9),
+            ("Gala", 858, 0),
+            ("Country Focus", 855, 4),
+            ("Audience Award", 854, 4),
+        ] {
+            cfg.strands.insert(
+                name.to_string(),
+                crate::config::StrandConfig {
+                    id,
+                    colour: "000000".to_string(),
+                    priority,
+                },
+            );
+        }
+        // 546 isn't a strand
+        let badges = [852, 546, 855, 854];
+        let names = cfg
+            .strands_from_badges(&badges)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["Audience Award", "Country Focus", "Official Selection"],
+            names
+        );
+        assert_eq!("Audience Award", cfg.strand_from_badges(badges.to_vec()).0);
+        assert_eq!("", cfg.strand_from_badges(vec![546]).0);
+
+        let evt = FestivalEvent {
+            strand: "Audience Award".to_string(),
+            strands: names
+                .iter()
+                .map(|name| StrandMarker {
+                    name: name.clone(),
+                    colour: "000000".to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let secondary = evt.secondary_strands();
+        assert_eq!(2, secondary.len());
+        assert_eq!("Country Focus", secondary[0].name);
+        assert!(evt.in_strand("official selection"));
+        assert!(!evt.in_strand("Gala"));
+    }
+    #[test]
+    fn test_badges() {
+        let mut cfg = Config::default();
+        for (name, id, symbol, extra_minutes) in [
+            ("Captioned", 546, "CC", 0),
+            ("Audio Described", 547, "AD", 0),
+            ("Q&A", 548, "Q&A", 30),
+            ("Introduced", 549, "Intro", 10),
+        ] {
+            cfg.badges.insert(
+                name.to_string(),
+                crate::config::BadgeConfig {
+                    id,
+                    symbol: symbol.to_string(),
+                    extra_minutes,
+                },
+            );
+        }
+        let evt = FestivalEvent {
+            badges: cfg.attributes_from_badges(&[852, 546, 547]),
+            ..Default::default()
+        };
+        assert_eq!(
+            vec!["AD", "CC"],
+            evt.badges
+                .iter()
+                .map(|b| b.symbol.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(evt.has_badge("captioned"));
+        assert!(evt.has_badge("ad"));
+        assert!(cfg.attributes_from_badges(&[852]).is_empty());
+        assert_eq!(0, cfg.extra_minutes_from_badges(&[546, 547]));
+        assert_eq!(40, cfg.extra_minutes_from_badges(&[546, 548, 549]));
+    }
+    #[test]
+    fn test_clashes() {
+        let at = |day: u32, start: (u32, u32), duration: i64, extra_minutes: u32| {
+            let start = NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap();
+            FestivalEvent {
+                date: NaiveDate::from_ymd_opt(2026, 2, day).unwrap(),
+                start,
+                end: start + chrono::TimeDelta::minutes(duration),
+                extra_minutes,
+                ..Default::default()
+            }
+        };
+        // Over by 20:00, unless the Q&A runs on
+        let film = at(25, (18, 0), 120, 0);
+        let next = at(25, (20, 0), 90, 0);
+        assert!(!film.clashes_with(&next));
+        let qa = at(25, (18, 0), 120, 30);
+        assert!(qa.clashes_with(&next));
+        assert!(next.clashes_with(&qa));
+        assert_eq!(120, qa.duration());
+        assert_eq!(
+            NaiveTime::from_hms_opt(20, 30, 0).unwrap(),
+            qa.adjusted_end()
+        );
+
+        // Past midnight into the next morning's first screening
+        let late = at(25, (22, 30), 120, 30);
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 2, 26)
+                .unwrap()
+                .and_hms_opt(1, 0, 0)
+                .unwrap(),
+            late.span().1
+        );
+        assert!(late.clashes_with(&at(26, (0, 45), 90, 0)));
+        assert!(!late.clashes_with(&at(26, (10, 0), 90, 0)));
+    }
+    #[test]
+    fn test_movie_record() {
+        let json = r#"{"data":{"movie":{"id":"33606","name":"Hamnet","posterImage":null,"synopsis":"","duration":125,"allGenres":"Drama, History","genre":"History","trailerYoutubeId":"xTY9GlNb5qE","year":2025,"country":"UK","language":"English","subtitles":null,"showings":[{"id":"1","screenId":"171","time":"2026-02-25T13:00:00Z","showingBadgeIds":[]}]}}}"#;
+        let screenings = deserialize_screenings(33606, json).unwrap();
+        let movie = screenings[0].movie.as_ref().unwrap();
+        assert_eq!(Some("2025".to_string()), movie.year);
+        assert_eq!(Some("History".to_string()), movie.genre);
+        assert_eq!(None, movie.subtitles);
+
+        let evt = FestivalEvent {
+            movie_id: 33606,
+            title: "Hamnet".to_string(),
+            genres: vec!["Drama".to_string(), "History".to_string()],
+            genre: "History".to_string(),
+            trailer_youtube_id: "xTY9GlNb5qE".to_string(),
+            year: "2025".to_string(),
+            country: "UK".to_string(),
+            certificate: "12A".to_string(),
+            ..Default::default()
+        };
+        assert_eq!("2025, UK, 12A", evt.details());
+        let entry = BrochureEntry::from_event(33606, &[evt]);
+        assert_eq!("History, Drama", entry.genres);
+        assert_eq!("https://www.youtube.com/watch?v=xTY9GlNb5qE", entry.trailer);
+    }
+    #[test]
+    fn test_into_typst() {
+        let evt = FestivalEvent {
+            date: NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            title: "Hamnet".to_string(),
+            attendees: vec!["Marion".to_string()],
+            ..Default::default()
+        };
+        let dict = summary::Dict::from(evt);
+        assert!(matches!(
+            dict.get("date").unwrap(),
+            summary::Value::Datetime(_)
+        ));
+        assert_eq!(
+            &summary::Value::Str("Hamnet".into()),
+            dict.get("title").unwrap()
+        );
+        assert!(matches!(
+            dict.get("attendees").unwrap(),
+            summary::Value::Array(_)
+        ));
+    }
+    #[test]
     fn test_markup() {
         assert_eq!(
-            "with _italics_ test".to_string(),
+            "with #emph[italics] test".to_string(),
             FestivalEvent::markup("with <i>italics</i> test").unwrap()
         );
         assert_eq!(
             "with #strong[bold] test \\* and \\$".to_string(),
             FestivalEvent::markup("with <b>bold</B> test * and $").unwrap()
         );
+        // Shapes of synopsis seen from GFT, with what each gives as markup and plain text
+        let synopses = [
+            (
+                "<p>Paul Mescal &amp; Jessie Buckley star in <i>Hamnet</i>.</p>",
+                "Paul Mescal & Jessie Buckley star in #emph[Hamnet].",
+                "Paul Mescal & Jessie Buckley star in Hamnet.",
+            ),
+            (
+                "<p><strong>SCOTTISH PREMIERE</strong><br>Followed by a Q&amp;A.</p>\r\n<p>__________</p><p>Contains strobe lighting</p>",
+                "#strong[SCOTTISH PREMIERE] \\\nFollowed by a Q&A.\n\n\\_\\_\\_\\_\\_\\_\\_\\_\\_\\_\n\nContains strobe lighting",
+                "SCOTTISH PREMIERE\nFollowed by a Q&A.\n\n__________\n\nContains strobe lighting",
+            ),
+            (
+                "<font face=\"Calibri\">Presented with <a href=\"https://www.gft.org.uk/\">GFT</a> &#8211; 50% off #1</font>",
+                "Presented with #link(\"https://www.gft.org.uk/\")[GFT] – 50% off \\#1",
+                "Presented with GFT (https://www.gft.org.uk/) – 50% off #1",
+            ),
+        ];
+        for (html, markup, plain) in synopses {
+            let event = FestivalEvent {
+                synopsis: FestivalEvent::markup(html).unwrap(),
+                synopsis_html: html.to_string(),
+                ..Default::default()
+            };
+            assert_eq!(markup, event.synopsis);
+            assert_eq!(plain, event.plain_synopsis());
+            assert_eq!(html, event.html_synopsis());
+        }
+        // Cached before the html was kept
+        let event = FestivalEvent {
+            synopsis: "with #strong[bold] & more".to_string(),
+            ..Default::default()
+        };
+        assert_eq!("<p>with bold &amp; more</p>", event.html_synopsis());
     }
 
     #[test]
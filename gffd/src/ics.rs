@@ -0,0 +1,203 @@
+use crate::films::FestivalEvent;
+use chrono::Utc;
+
+// Screening times from GFT are Glasgow local times
+const TZID: &str = "Europe/London";
+const VTIMEZONE: &[&str] = &[
+    "BEGIN:VTIMEZONE",
+    "TZID:Europe/London",
+    "BEGIN:DAYLIGHT",
+    "TZOFFSETFROM:+0000",
+    "TZOFFSETTO:+0100",
+    "TZNAME:BST",
+    "DTSTART:19700329T010000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+    "END:DAYLIGHT",
+    "BEGIN:STANDARD",
+    "TZOFFSETFROM:+0100",
+    "TZOFFSETTO:+0000",
+    "TZNAME:GMT",
+    "DTSTART:19701025T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+/// Build an RFC 5545 calendar containing one VEVENT per screening.
+pub fn calendar(name: &str, events: &[&FestivalEvent]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//gffd//GFF 2026//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+        format!("X-WR-TIMEZONE:{}", TZID),
+    ];
+    lines.extend(VTIMEZONE.iter().map(|e| e.to_string()));
+    for evt in events {
+        lines.extend(vevent(evt, &stamp));
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|e| fold(e)).collect::<String>()
+}
+
+fn vevent(evt: &FestivalEvent, stamp: &str) -> Vec<String> {
+    let (start, end) = evt.span();
+    let going = if evt.attendees.is_empty() {
+        "".to_string()
+    } else {
+        format!("Going: {}", evt.attendees.join(", "))
+    };
+    let trailer = if evt.trailer_youtube_id.is_empty() {
+        "".to_string()
+    } else {
+        format!("Trailer: {}", evt.trailer_url())
+    };
+    let badges = evt
+        .badges
+        .iter()
+        .map(|b| b.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    // The event runs on for any Q&A, so say how long the film itself is
+    let running_time = if evt.extra_minutes == 0 {
+        "".to_string()
+    } else {
+        format!("Running time: {}", evt.running_time())
+    };
+    let description = [
+        going,
+        badges,
+        running_time,
+        evt.details(),
+        evt.plain_synopsis(),
+        trailer,
+    ]
+    .into_iter()
+    .filter(|e| !e.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n");
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid(evt)),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART;TZID={}:{}", TZID, start.format("%Y%m%dT%H%M%S")),
+        format!("DTEND;TZID={}:{}", TZID, end.format("%Y%m%dT%H%M%S")),
+        format!("SUMMARY:{}", escape(&evt.title)),
+        format!("LOCATION:{}", escape(&evt.screen)),
+        format!("DESCRIPTION:{}", escape(&description)),
+    ];
+    let mut categories = vec![escape(&evt.strand)];
+    categories.extend(evt.secondary_strands().iter().map(|s| escape(&s.name)));
+    if !evt.strand.is_empty() {
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// Screening ids are stable across GFT fetches, so they make a stable UID.
+pub fn uid(evt: &FestivalEvent) -> String {
+    format!("screening-{}-{}@gffd", evt.movie_id, evt.screening_id)
+}
+
+/// Escape a TEXT value - backslash, semicolon, comma and newlines.
+pub fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets, never splitting a UTF-8 character, and
+/// terminate it with CRLF.
+pub fn fold(line: &str) -> String {
+    let mut result = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            result.push_str("\r\n ");
+            width = 1;
+        }
+        result.push(c);
+        width += len;
+    }
+    result.push_str("\r\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            "Tom\\, Jerry\\; and \\\\ more\\nnext",
+            escape("Tom, Jerry; and \\ more\nnext")
+        );
+    }
+
+    #[test]
+    fn test_calendar() {
+        let evt = FestivalEvent {
+            date: chrono::NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            start: chrono::NaiveTime::from_hms_opt(23, 15, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(1, 5, 0).unwrap(),
+            movie_id: 33606,
+            screening_id: 12,
+            title: "Me, Myself; I".to_string(),
+            screen: "GFT 1".to_string(),
+            synopsis: "A #strong[bold] film".to_string(),
+            ..Default::default()
+        };
+        let ics = calendar("GFF", &[&evt]);
+        assert!(ics.contains("UID:screening-33606-12@gffd\r\n"));
+        assert!(ics.contains("DTSTART;TZID=Europe/London:20260225T231500\r\n"));
+        assert!(ics.contains("DTEND;TZID=Europe/London:20260226T010500\r\n"));
+        assert!(ics.contains("SUMMARY:Me\\, Myself\\; I\r\n"));
+        assert!(ics.contains("LOCATION:GFT 1\r\n"));
+        assert!(ics.contains("DESCRIPTION:A bold film\r\n"));
+        assert!(ics.contains("BEGIN:VTIMEZONE\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+
+        let evt = FestivalEvent {
+            year: "2025".to_string(),
+            language: "French".to_string(),
+            subtitles: "English subtitles".to_string(),
+            trailer_youtube_id: "abc".to_string(),
+            ..evt
+        };
+        let ics = calendar("GFF", &[&evt]).replace("\r\n ", "");
+        assert!(ics.contains(
+            "DESCRIPTION:2025\\, French\\, English subtitles\\n\\nA bold film\\n\\n\
+             Trailer: https://www.youtube.com/watch?v=abc\r\n"
+        ));
+
+        // A Q&A keeps the event going, though the film is as long as ever
+        let evt = FestivalEvent {
+            extra_minutes: 30,
+            ..evt
+        };
+        let ics = calendar("GFF", &[&evt]).replace("\r\n ", "");
+        assert!(ics.contains("DTEND;TZID=Europe/London:20260226T013500\r\n"));
+        assert!(ics.contains("Running time: 110 min + 30 min after\\n"));
+    }
+
+    #[test]
+    fn test_fold() {
+        let line = "X".repeat(80);
+        let folded = fold(&line);
+        assert_eq!(
+            format!("{}\r\n {}\r\n", "X".repeat(75), "X".repeat(5)),
+            folded
+        );
+        let multibyte = format!("{}é", "X".repeat(74));
+        assert_eq!(format!("{}\r\n é\r\n", "X".repeat(74)), fold(&multibyte));
+    }
+}
@@ -0,0 +1,363 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+// Prefix of the environment variables that override config, with __ between the
+// parts of a nested field, such as GFFD_SERVER_OPTIONS__PORT
+const ENV_PREFIX: &str = "GFFD_";
+
+/// Where a config value came from, from the lowest precedence to the highest.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Default,
+    System(String),
+    File(String),
+    Env(String),
+    Cli(String),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::System(file) => write!(f, "system file {}", file),
+            Source::File(file) => write!(f, "file {}", file),
+            Source::Env(var) => write!(f, "environment {}", var),
+            Source::Cli(flag) => write!(f, "command line {}", flag),
+        }
+    }
+}
+
+/// The text of a json or toml config file, going by its extension.
+pub fn parse(file: &Path, text: &str) -> Result<Value, String> {
+    if is_toml(file) {
+        toml::from_str(text).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    }
+}
+
+/// Write a json or toml config file, going by its extension.
+pub fn write_file(file: &Path, value: &Value) -> Result<(), String> {
+    let text = if is_toml(file) {
+        toml::to_string_pretty(value).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(value).map_err(|e| e.to_string())? + "\n"
+    };
+    fs::write(file, text).map_err(|e| e.to_string())
+}
+
+fn is_toml(file: &Path) -> bool {
+    file.extension().is_some_and(|e| e == "toml")
+}
+
+/// The config.json or config.toml in the directory, if there is one.  Having both is an
+/// error, as which one wins would be a surprise.
+pub fn find_file(directory: &Path) -> Result<Option<String>, String> {
+    let found = ["config.json", "config.toml"]
+        .iter()
+        .map(|name| directory.join(name))
+        .filter(|file| file.exists())
+        .map(|file| file.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    match &found[..] {
+        [] => Ok(None),
+        [file] => Ok(Some(file.clone())),
+        _ => Err(found.join(" and ")),
+    }
+}
+
+/// A config being built up one layer at a time, remembering where each value came
+/// from.  Objects are merged key by key, anything else is replaced.
+#[derive(Debug)]
+pub struct Layers {
+    pub value: Value,
+    pub sources: BTreeMap<Vec<String>, Source>,
+}
+
+impl Layers {
+    pub fn new(defaults: Value) -> Self {
+        let mut layers = Self {
+            value: Value::Object(Map::new()),
+            sources: BTreeMap::new(),
+        };
+        layers.overlay(&defaults, &Source::Default);
+        layers
+    }
+
+    pub fn overlay(&mut self, layer: &Value, source: &Source) {
+        let mut path = vec![];
+        self.merge(&mut path, layer, source);
+    }
+
+    fn merge(&mut self, path: &mut Vec<String>, layer: &Value, source: &Source) {
+        match layer {
+            // Only merged into an object already there, anything new comes from this layer
+            // as a whole, such as a screen
+            Value::Object(fields)
+                if !fields.is_empty() && self.get(path).is_some_and(Value::is_object) =>
+            {
+                for (k, v) in fields {
+                    path.push(k.clone());
+                    self.merge(path, v, source);
+                    path.pop();
+                }
+            }
+            // An empty object adds nothing to one that is already there
+            Value::Object(_) if self.get(path).is_some_and(Value::is_object) => {}
+            _ => self.set(path, layer.clone(), source.clone()),
+        }
+    }
+
+    /// Where the value at `path` came from, going by the nearest parent with a source if
+    /// the value itself doesn't have one.
+    pub fn source_of(&self, path: &[String]) -> Option<&Source> {
+        (0..=path.len())
+            .rev()
+            .find_map(|depth| self.sources.get(&path[..depth]))
+    }
+
+    pub fn get(&self, path: &[String]) -> Option<&Value> {
+        path.iter().try_fold(&self.value, |v, k| v.get(k))
+    }
+
+    /// Set the value at `path`, which replaces where anything under it came from.
+    pub fn set(&mut self, path: &[String], value: Value, source: Source) {
+        // An object above that came from one place still has the rest of its fields from it
+        for depth in 0..path.len() {
+            let parent = &path[..depth];
+            let Some(from) = self.sources.remove(parent) else {
+                continue;
+            };
+            let keys = match self.get(parent) {
+                Some(Value::Object(fields)) => fields.keys().cloned().collect(),
+                _ => vec![],
+            };
+            for k in keys {
+                let mut field = parent.to_vec();
+                field.push(k);
+                self.sources.insert(field, from.clone());
+            }
+        }
+        let Some((last, parents)) = path.split_last() else {
+            self.value = value;
+            self.sources.clear();
+            self.sources.insert(vec![], source);
+            return;
+        };
+        let mut target = &mut self.value;
+        for k in parents {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            target = target
+                .as_object_mut()
+                .unwrap()
+                .entry(k.clone())
+                .or_insert(Value::Object(Map::new()));
+        }
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        target.as_object_mut().unwrap().insert(last.clone(), value);
+        self.sources.retain(|p, _| !p.starts_with(path));
+        self.sources.insert(path.to_vec(), source);
+    }
+
+    /// Apply the GFFD_ variables that name a field.  Where the field is text the value
+    /// is taken as it is, otherwise it is read as json, so numbers, true and false, and
+    /// whole objects such as the screens can be given.
+    pub fn overlay_env(&mut self, vars: impl Iterator<Item = (String, String)>) {
+        let mut vars = vars
+            .filter_map(|(k, v)| Some((k.strip_prefix(ENV_PREFIX)?.to_string(), k.clone(), v)))
+            .collect::<Vec<_>>();
+        // Parents before their fields, so GFFD_SERVER_OPTIONS__PORT isn't lost
+        vars.sort();
+        for (name, var, text) in vars {
+            let Some(path) = self.env_path(&name) else {
+                continue;
+            };
+            let value = match self.get(&path) {
+                Some(Value::String(_)) => Value::String(text),
+                _ => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+            };
+            self.set(&path, value, Source::Env(var));
+        }
+    }
+
+    // The field a variable names, matching the keys already there ignoring case.  The
+    // first part has to be a known field, so unrelated GFFD_ variables are left alone.
+    fn env_path(&self, name: &str) -> Option<Vec<String>> {
+        let mut path: Vec<String> = vec![];
+        for (i, part) in name.split("__").enumerate() {
+            let here = self.get(&path);
+            let key = here
+                .and_then(Value::as_object)
+                .and_then(|o| o.keys().find(|k| k.eq_ignore_ascii_case(part)).cloned());
+            match key {
+                Some(key) => path.push(key),
+                None if i == 0 => return None,
+                None => path.push(part.to_lowercase()),
+            }
+        }
+        Some(path)
+    }
+
+    /// Replace every "!env VAR" text with the value of the variable.
+    pub fn substitute_env(&mut self) {
+        substitute(&mut self.value);
+    }
+}
+
+/// Each value with where it came from, one per line in path order.
+pub fn describe(sources: &BTreeMap<Vec<String>, Source>, value: &Value) -> String {
+    sources
+        .iter()
+        .filter_map(|(path, source)| {
+            let v = path.iter().try_fold(value, |v, k| v.get(k))?;
+            Some(format!("{} = {}  ({})\n", path.join("."), v, source))
+        })
+        .collect()
+}
+
+fn substitute(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            if let Some(var) = text.strip_prefix("!env ") {
+                let val = std::env::var(var.trim()).unwrap_or_default();
+                if val.is_empty() {
+                    eprintln!("Environment variable {} is unset or empty", var.trim());
+                }
+                *text = val;
+            }
+        }
+        Value::Object(fields) => fields.values_mut().for_each(substitute),
+        Value::Array(items) => items.iter_mut().for_each(substitute),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path(p: &str) -> Vec<String> {
+        p.split('.').map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut layers = Layers::new(json!({
+            "cookie": "",
+            "server_options": {"port": 3020, "callback_url": "https://gff.darach.org.uk"},
+            "screens": {},
+            "names": {}
+        }));
+        let system = Source::System("/etc/gffd/config.toml".to_string());
+        layers.overlay(
+            &json!({"screens": {"GFT 1": {"id": 171, "colour": 3}}}),
+            &system,
+        );
+        let file = Source::File("config.json".to_string());
+        layers.overlay(
+            &json!({
+                "server_options": {"callback_url": "http://localhost"},
+                "screens": {"Odeon 10": {"id": 469, "colour": 7}},
+                "names": {}
+            }),
+            &file,
+        );
+        layers.overlay_env(
+            [
+                ("GFFD_SERVER_OPTIONS__PORT", "3021"),
+                ("GFFD_COOKIE", "123"),
+                ("GFFD_NAMES", r#"{"N": "Neil"}"#),
+                ("GFFD_SYSTEM_CONFIG", "/tmp/gffd.toml"),
+                ("HOME", "/root"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string())),
+        );
+        layers.set(
+            &path("server_options.callback_url"),
+            json!("https://example.org"),
+            Source::Cli("--url".to_string()),
+        );
+
+        assert_eq!(
+            json!({
+                "cookie": "123",
+                "server_options": {"port": 3021, "callback_url": "https://example.org"},
+                "screens": {
+                    "GFT 1": {"id": 171, "colour": 3},
+                    "Odeon 10": {"id": 469, "colour": 7}
+                },
+                "names": {"N": "Neil"}
+            }),
+            layers.value
+        );
+        let source = |p: &str| layers.sources.get(&path(p)).cloned();
+        assert_eq!(
+            Some(Source::Env("GFFD_COOKIE".to_string())),
+            source("cookie")
+        );
+        assert_eq!(
+            Some(Source::Env("GFFD_SERVER_OPTIONS__PORT".to_string())),
+            source("server_options.port")
+        );
+        assert_eq!(Some(system), source("screens.GFT 1"));
+        assert_eq!(Some(file), source("screens.Odeon 10"));
+        assert_eq!(None, source("screens"));
+        assert_eq!(
+            Some(&Source::System("/etc/gffd/config.toml".to_string())),
+            layers.source_of(&path("screens.GFT 1.colour"))
+        );
+        assert_eq!(None, layers.source_of(&path("screens")));
+        assert_eq!(
+            Some(Source::Cli("--url".to_string())),
+            source("server_options.callback_url")
+        );
+        assert!(describe(&layers.sources, &layers.value)
+            .contains("server_options.port = 3021  (environment GFFD_SERVER_OPTIONS__PORT)\n"));
+    }
+
+    #[test]
+    fn test_substitute_env() {
+        let mut layers = Layers::new(json!({
+            "cookie": "!env GFFD_TEST_LAYERS_UNSET",
+            "server_options": {"feed_tokens": {"Neil": "!env GFFD_TEST_LAYERS_UNSET"}},
+            "kept": "plain"
+        }));
+        layers.substitute_env();
+        assert_eq!(json!(""), layers.value["cookie"]);
+        assert_eq!(
+            json!(""),
+            layers.value["server_options"]["feed_tokens"]["Neil"]
+        );
+        assert_eq!(json!("plain"), layers.value["kept"]);
+    }
+
+    #[test]
+    fn test_files() {
+        let dir = std::env::temp_dir().join(format!("gffd-layers-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(Ok(None), find_file(&dir));
+        let toml = dir.join("config.toml");
+        let value = json!({"cookie": "", "screens": {"GFT 1": {"id": 171, "colour": 3}}});
+        write_file(&toml, &value).unwrap();
+        assert_eq!(
+            value,
+            parse(&toml, &fs::read_to_string(&toml).unwrap()).unwrap()
+        );
+        assert_eq!(
+            Ok(Some(toml.to_string_lossy().to_string())),
+            find_file(&dir)
+        );
+        write_file(&dir.join("config.json"), &value).unwrap();
+        assert!(find_file(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,31 @@
 mod args;
 mod calendar;
+mod check;
 mod config;
+mod discover;
+mod export;
 mod films;
-use crate::args::{Args, GlobalOptions, Subcommands};
-use crate::calendar::{filter_summary, sync_events, upload_events};
-use crate::config::Config;
+mod ics;
+mod layers;
+mod posters;
+mod publish;
+mod render;
+mod server;
+mod xlsx;
+use crate::args::{
+    Args, ConfigAction, ExportFormat, GlobalOptions, PosterProfile, PublishDocument,
+    RenderDocument, RenderOptions, Subcommands,
+};
+use crate::calendar::{fill_attendance, filter_summary, sync_events, upload_events};
+use crate::config::{CliValue, Config};
 use crate::films::{fetch_ids, id_map, load_ids, BrochureEntry, FestivalEvent, SummaryEntry};
+use crate::export::{csv, select, write_output, Column};
+use crate::ics::calendar;
+use crate::server::serve;
+use crate::xlsx::workbook;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::path::Path;
 
 #[tokio::main]
 async fn main() {
@@ -22,7 +41,33 @@ async fn main() {
         ref debug,
         ref live,
     } = args.options;
-    let mut config = match Config::read_config_file(directory) {
+    // The command line is the last layer of the config
+    let mut cli = vec![];
+    let mut given = |path: &'static str, flag: &'static str, value: serde_json::Value| {
+        cli.push(CliValue { path, flag, value })
+    };
+    if !auth_file.is_empty() {
+        given("calendar_auth_file", "--auth", auth_file.clone().into());
+    }
+    if !calendar_main_id.is_empty() {
+        given("calendar_main_id", "--main", calendar_main_id.trim().into());
+    }
+    if !calendar_filter_id.is_empty() {
+        given("calendar_filter_id", "--fiter", calendar_filter_id.trim().into());
+    }
+    if let Subcommands::Serve {
+        port,
+        ref callback_url,
+    } = args.subcommand
+    {
+        if !callback_url.is_empty() {
+            given("server_options.callback_url", "--url", callback_url.clone().into());
+        }
+        if port >= 0 {
+            given("server_options.port", "--port", port.into());
+        }
+    }
+    let mut config = match Config::read_config_file(directory, &cli) {
         Err(e) => {
             println!("gffd: {}", e);
             return;
@@ -36,27 +81,50 @@ async fn main() {
     if *live {
         config.set_live();
     }
-
-    if !auth_file.is_empty() {
-        config.set_auth_file(auth_file);
-    }
-    if !calendar_main_id.is_empty() {
-        config.calendar_main_id = calendar_main_id.clone().trim().to_owned();
-    }
-    if !calendar_filter_id.is_empty() {
-        config.calendar_filter_id = calendar_filter_id.clone().trim().to_owned();
-    }
     match args.subcommand {
-        Subcommands::Serve { port, callback_url } => {
-            if !callback_url.is_empty() {
-                config.server_options.callback_url = callback_url;
-            }
-            if port >= 0 {
-                config.server_options.port = port as u16;
+        Subcommands::Serve { .. } => {
+            if let Err(e) = serve(config).await {
+                println!("gffd: {}", e);
             }
         }
         Subcommands::ShowConfig {} => {
-            println!("{:?}", &config);
+            print!("{}", config.describe());
+        }
+        Subcommands::Config { action } => match action {
+            ConfigAction::Check {} => match check::check_file(&config) {
+                Ok(problems) if problems.is_empty() => {
+                    println!("{} is fine", config.config_file());
+                }
+                Ok(problems) => {
+                    for problem in problems {
+                        println!("{}", problem);
+                    }
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    println!("gffd: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            ConfigAction::Schema { output } => {
+                if let Err(e) = write_output(&output, check::schema().as_bytes()) {
+                    println!("gffd: {}", e);
+                }
+            }
+        },
+        Subcommands::Discover { dry_run } => {
+            let result = tokio::task::spawn_blocking(move || {
+                let catalogue = discover::Catalogue::fetch()?;
+                let events = FestivalEvent::fetch_all(&config)
+                    .map_err(|e| discover::DiscoverError::FetchError(e.to_string()))?;
+                discover::discover(&config, &catalogue, &events, dry_run)
+            })
+            .await
+            .unwrap();
+            match result {
+                Ok(discovery) => print!("{}", discovery),
+                Err(e) => println!("gffd: {}", e),
+            }
         }
         Subcommands::Sync {} => {
             let map = id_map(&config).unwrap();
@@ -66,8 +134,12 @@ async fn main() {
                 .filter_map(|e| FestivalEvent::fetch_from_gft(&config, *e).ok())
                 .flatten()
                 .collect::<Vec<_>>();
-            let (modified, deleted) = sync_events(&config, &events[..]).await;
-            println!("Sync done.  Modified {}, deleted {}", modified, deleted);
+            match sync_events(&config, &events[..]).await {
+                Ok((modified, deleted)) => {
+                    println!("Sync done.  Modified {}, deleted {}", modified, deleted)
+                }
+                Err(e) => println!("gffd: {}", e),
+            }
         }
 
         Subcommands::Upload {} => {
@@ -78,11 +150,13 @@ async fn main() {
                 .filter_map(|e| FestivalEvent::fetch_from_gft(&config, *e).ok())
                 .flatten()
                 .collect::<Vec<_>>();
-            let (added, deleted) = upload_events(&config, &events[..]).await;
-            println!(
-                "Upload done.  Uploaded {} events and deleted {}",
-                added, deleted
-            );
+            match upload_events(&config, &events[..]).await {
+                Ok((added, deleted)) => println!(
+                    "Upload done.  Uploaded {} events and deleted {}",
+                    added, deleted
+                ),
+                Err(e) => println!("gffd: {}", e),
+            }
         }
         Subcommands::FetchScreenings { id } => {
             if let Ok(id) = id.parse::<u32>() {
@@ -105,12 +179,10 @@ async fn main() {
         }
         Subcommands::List {} => {}
         Subcommands::FilterSummary {} => {
-            let entries = filter_summary(&config)
-                .await
-                .into_iter()
-                .map(|e| e)
-                .collect::<Vec<_>>();
-            println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+            match filter_summary(&config).await {
+                Ok(entries) => println!("{}", serde_json::to_string_pretty(&entries).unwrap()),
+                Err(e) => println!("gffd: {}", e),
+            }
         }
         Subcommands::Summary {} => {
             let map = id_map(&config).unwrap();
@@ -142,6 +214,186 @@ async fn main() {
             showings.sort_by(|a, b| a.sortname.cmp(&b.sortname));
             println!("{}", serde_json::to_string_pretty(&showings).unwrap());
         }
+        Subcommands::Export { format } => match format {
+            ExportFormat::Ics { filter, output } => {
+                let Some(mut events) = fetch_events(&config).await else {
+                    return;
+                };
+                if filter.needs_attendance() && !with_attendance(&config, &mut events).await {
+                    return;
+                }
+                let ics = calendar("GFF 2026", &select(&events, &filter));
+                if let Err(e) = write_output(&output, ics.as_bytes()) {
+                    println!("gffd: {}", e);
+                }
+            }
+            ExportFormat::Csv {
+                filter,
+                output,
+                layout,
+                columns,
+            } => {
+                let Some(mut events) = fetch_events(&config).await else {
+                    return;
+                };
+                if filter.needs_attendance() && !with_attendance(&config, &mut events).await {
+                    return;
+                }
+                let columns = if columns.is_empty() {
+                    Ok(layout.columns())
+                } else {
+                    columns
+                        .iter()
+                        .map(|c| Column::from_header(c))
+                        .collect::<Result<Vec<_>, _>>()
+                };
+                if let Err(e) = columns
+                    .and_then(|columns| csv(&select(&events, &filter), &columns))
+                    .and_then(|data| write_output(&output, &data))
+                {
+                    println!("gffd: {}", e);
+                }
+            }
+            ExportFormat::Xlsx { filter, output } => {
+                let Some(mut events) = fetch_events(&config).await else {
+                    return;
+                };
+                if filter.needs_attendance() && !with_attendance(&config, &mut events).await {
+                    return;
+                }
+                if let Err(e) = workbook(&select(&events, &filter))
+                    .and_then(|data| write_output(&output, &data))
+                {
+                    println!("gffd: {}", e);
+                }
+            }
+        },
+        Subcommands::Render { mut document } => {
+            if let Err(e) = document.fill_version(&config) {
+                println!("gffd: {}", e);
+                return;
+            }
+            let Some(mut events) = fetch_events(&config).await else {
+                return;
+            };
+            let result = match document {
+                RenderDocument::Brochure {
+                    options,
+                    profile,
+                    grid,
+                    format,
+                } => {
+                    // Missing posters are fetched with a blocking client
+                    tokio::task::spawn_blocking(move || {
+                        let output =
+                            options.output_or(&format!("brochure.{}", format.extension()));
+                        render::brochure(&config, &events, &options, profile, grid, format)
+                            .and_then(|pdf| summary::save_pdf(&pdf, Path::new(&output)))
+                    })
+                    .await
+                    .unwrap()
+                }
+                RenderDocument::Summary { options } => {
+                    render::summary(&config, &events, &options).and_then(|pdf| {
+                        summary::save_pdf(&pdf, Path::new(&options.output_or("summary.pdf")))
+                    })
+                }
+                RenderDocument::PersonSummary { options } => {
+                    if !with_attendance(&config, &mut events).await {
+                        return;
+                    }
+                    render::person_summary(&config, &events, &options).and_then(|pdf| {
+                        summary::save_pdf(&pdf, Path::new(&options.output_or("person-summary.pdf")))
+                    })
+                }
+                RenderDocument::FilterSummary {
+                    options,
+                    people,
+                    per_person,
+                } => {
+                    if !with_attendance(&config, &mut events).await {
+                        return;
+                    }
+                    let output = options.output_or("filter-summary.pdf");
+                    if per_person {
+                        render::filter_summaries(&config, &events, &options, &people)
+                            .and_then(|pdfs| {
+                                pdfs.iter().try_for_each(|(title, pdf)| {
+                                    summary::save_pdf(pdf, &render::person_path(&output, title))
+                                })
+                            })
+                    } else {
+                        render::filter_summary(&config, &events, &options, &people)
+                            .and_then(|pdf| summary::save_pdf(&pdf, Path::new(&output)))
+                    }
+                }
+            };
+            if let Err(e) = result {
+                println!("gffd: {}", e);
+            }
+        }
+        Subcommands::Publish {
+            document,
+            templates,
+            grid,
+        } => {
+            if config.publish_directory.is_empty() {
+                println!("gffd: {}", publish::PublishError::NoDirectory);
+                return;
+            }
+            let Some(mut events) = fetch_events(&config).await else {
+                return;
+            };
+            if let PublishDocument::Summaries = document
+                && !with_attendance(&config, &mut events).await
+            {
+                return;
+            }
+            let result = tokio::task::spawn_blocking(move || {
+                let options = |version: &str| RenderOptions {
+                    output: "".to_string(),
+                    doc_version: version.to_string(),
+                    templates: templates.clone(),
+                };
+                // The entries of the last brochure rendered, which is the one published
+                let entries = RefCell::new(vec![]);
+                let dir = Path::new(&config.publish_directory);
+                let release = publish::publish(dir, document, |version| match document {
+                    PublishDocument::Brochure => {
+                        let options = options(version);
+                        let inputs = render::brochure_inputs(
+                            &config,
+                            &events,
+                            &options,
+                            PosterProfile::Print,
+                            grid,
+                        );
+                        *entries.borrow_mut() = inputs.entries.clone();
+                        summary::brochure_pdf(inputs, options.template_dir())
+                    }
+                    PublishDocument::Summaries => {
+                        render::filter_summary(&config, &events, &options(version), &[])
+                    }
+                })?;
+                if let (Some(release), PublishDocument::Brochure) = (&release, document) {
+                    let version = release.version.to_string();
+                    render::save_published(&config, &version, &entries.borrow())?;
+                }
+                Ok::<_, publish::PublishError>(release)
+            })
+            .await
+            .unwrap();
+            match result {
+                Ok(Some(release)) => println!(
+                    "Published {} v{} as {}",
+                    document.name(),
+                    release.version,
+                    release.file
+                ),
+                Ok(None) => println!("{} is unchanged, nothing published", document.name()),
+                Err(e) => println!("gffd: {}", e),
+            }
+        }
         Subcommands::Ids {} => {
             let map = tokio::task::spawn_blocking(move || id_map(&config))
                 .await
@@ -150,3 +402,28 @@ async fn main() {
         }
     };
 }
+
+// reqwest is blocking, so the screenings are fetched off the runtime.  None once the
+// error has been reported.
+async fn fetch_events(config: &Config) -> Option<Vec<FestivalEvent>> {
+    let blocking_config = config.clone();
+    let result = tokio::task::spawn_blocking(move || FestivalEvent::fetch_all(&blocking_config))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|events| events.map_err(|e| e.to_string()));
+    if let Err(e) = &result {
+        println!("gffd: {}", e);
+    }
+    result.ok()
+}
+
+// Fill in who is going from the main calendar.  False once the error has been reported.
+async fn with_attendance(config: &Config, events: &mut [FestivalEvent]) -> bool {
+    match fill_attendance(config, events).await {
+        Ok(()) => true,
+        Err(e) => {
+            println!("gffd: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::args::PosterProfile;
+use crate::config::Config;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+// brochure.typ shows posters 75pt wide, at the 2:3 shape they are fetched in
+const POSTER_WIDTH_PT: f32 = 75.0;
+// Anything smaller than this is a broken download rather than a poster
+const MIN_SIZE: u32 = 50;
+
+#[derive(Error, Debug)]
+pub enum PosterError {
+    // Unable to read the original poster
+    #[error("Unable to read {0}")]
+    ReadError(String),
+    // Not an image, or too small to be one
+    #[error("{0} is not a usable image - {1}")]
+    InvalidImage(String, String),
+    // Unable to save the resized poster
+    #[error("Unable to write {0} - {1}")]
+    WriteError(String, String),
+}
+
+impl PosterProfile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PosterProfile::Print => "print",
+            PosterProfile::Phone => "phone",
+        }
+    }
+
+    pub fn dpi(&self) -> u32 {
+        match self {
+            PosterProfile::Print => 300,
+            PosterProfile::Phone => 110,
+        }
+    }
+
+    pub fn quality(&self) -> u8 {
+        match self {
+            PosterProfile::Print => 85,
+            PosterProfile::Phone => 60,
+        }
+    }
+
+    /// The largest the poster needs to be, in pixels, to fill its space at the dpi.
+    pub fn size(&self) -> (u32, u32) {
+        let width = (POSTER_WIDTH_PT / 72.0 * self.dpi() as f32).round() as u32;
+        (width, width * 3 / 2)
+    }
+}
+
+/// Decode the image, rejecting anything that isn't plausibly a poster.
+pub fn decode(name: &str, data: &[u8]) -> Result<DynamicImage, PosterError> {
+    let img = image::load_from_memory(data)
+        .map_err(|e| PosterError::InvalidImage(name.to_string(), format!("{}", e)))?;
+    if img.width() < MIN_SIZE || img.height() < MIN_SIZE {
+        return Err(PosterError::InvalidImage(
+            name.to_string(),
+            format!("only {}x{}", img.width(), img.height()),
+        ));
+    }
+    Ok(img)
+}
+
+/// Shrink the poster to fit the profile's size, never enlarging it, and recompress it
+/// as a jpeg.
+pub fn process(name: &str, data: &[u8], profile: PosterProfile) -> Result<Vec<u8>, PosterError> {
+    let mut img = decode(name, data)?;
+    let (width, height) = profile.size();
+    if img.width() > width || img.height() > height {
+        img = img.resize(width, height, FilterType::Lanczos3);
+    }
+    let mut out = vec![];
+    JpegEncoder::new_with_quality(&mut out, profile.quality())
+        .encode_image(&img.to_rgb8())
+        .map_err(|e| PosterError::WriteError(name.to_string(), format!("{}", e)))?;
+    Ok(out)
+}
+
+/// The poster for `key` prepared for the profile, as a path relative to the state
+/// directory.  Variants are cached in posters/<profile> and remade whenever the
+/// original is newer.
+pub fn variant(cfg: &Config, key: &str, profile: PosterProfile) -> Result<String, PosterError> {
+    let original = format!("{}/posters/{}.jpg", &cfg.state_directory, key);
+    let relative = format!("posters/{}/{}.jpg", profile.name(), key);
+    let cached = Path::new(&cfg.state_directory).join(&relative);
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    if let (Some(made), Some(changed)) = (modified(&cached), modified(Path::new(&original)))
+        && made >= changed
+    {
+        return Ok(relative);
+    }
+    let data = fs::read(&original).map_err(|_| PosterError::ReadError(original.clone()))?;
+    let resized = process(&original, &data, profile)?;
+    if let Some(dir) = cached.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| PosterError::WriteError(dir.display().to_string(), format!("{}", e)))?;
+    }
+    fs::write(&cached, resized)
+        .map_err(|e| PosterError::WriteError(cached.display().to_string(), format!("{}", e)))?;
+    Ok(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn poster(width: u32, height: u32) -> Vec<u8> {
+        let img = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        });
+        let mut out = Cursor::new(vec![]);
+        img.write_to(&mut out, ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!((313, 469), PosterProfile::Print.size());
+        assert_eq!((115, 172), PosterProfile::Phone.size());
+    }
+
+    #[test]
+    fn test_process() {
+        let original = poster(400, 600);
+        let phone = process("test", &original, PosterProfile::Phone).unwrap();
+        let img = decode("phone", &phone).unwrap();
+        assert_eq!((115, 172), (img.width(), img.height()));
+
+        // Small posters are recompressed but not enlarged
+        let small = process("test", &poster(100, 150), PosterProfile::Print).unwrap();
+        let img = decode("small", &small).unwrap();
+        assert_eq!((100, 150), (img.width(), img.height()));
+
+        assert!(decode("tiny", &poster(10, 10)).is_err());
+        assert!(process("junk", b"<html>Not found</html>", PosterProfile::Print).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::args::PublishDocument;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use summary::RenderError;
+use thiserror::Error;
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Error, Debug)]
+pub enum PublishError {
+    // No publish_directory in config.json
+    #[error("publish_directory is not set in config.json")]
+    NoDirectory,
+    // The manifest exists but can't be used
+    #[error("{0} is not a valid manifest - {1}")]
+    InvalidManifest(String, String),
+    // Unable to write a pdf, the manifest or the latest link
+    #[error("Unable to write {0} - {1}")]
+    WriteError(String, String),
+    // Rendering the document failed
+    #[error(transparent)]
+    Render(#[from] RenderError),
+}
+
+impl PublishDocument {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PublishDocument::Brochure => "brochure",
+            PublishDocument::Summaries => "summaries",
+        }
+    }
+}
+
+/// One published version of a document.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    pub version: u32,
+    pub file: String,
+    // RFC 3339, UTC
+    pub published: String,
+    // blake3 of the pdf
+    pub hash: String,
+}
+
+/// Every version published of each document, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub documents: BTreeMap<String, Vec<Release>>,
+}
+
+fn write_error(path: &Path) -> impl Fn(std::io::Error) -> PublishError + '_ {
+    move |e| PublishError::WriteError(path.display().to_string(), format!("{}", e))
+}
+
+// Write to a temporary name and rename it into place, so readers never see half a file
+fn write_atomic(path: &Path, data: &[u8]) -> Result<(), PublishError> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data).map_err(write_error(&tmp))?;
+    fs::rename(&tmp, path).map_err(write_error(path))
+}
+
+impl Manifest {
+    pub fn load(dir: &Path) -> Result<Self, PublishError> {
+        let path = dir.join(MANIFEST);
+        match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                PublishError::InvalidManifest(path.display().to_string(), format!("{}", e))
+            }),
+            Err(_) => Ok(Manifest::default()),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), PublishError> {
+        let path = dir.join(MANIFEST);
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| PublishError::WriteError(path.display().to_string(), format!("{}", e)))?;
+        write_atomic(&path, &json)
+    }
+
+    pub fn latest(&self, document: PublishDocument) -> Option<&Release> {
+        self.documents.get(document.name())?.last()
+    }
+}
+
+pub fn hash(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+}
+
+/// The version `document` will be published as next, 1 if it never has been.
+pub fn next_version(dir: &Path, document: PublishDocument) -> Result<u32, PublishError> {
+    Ok(Manifest::load(dir)?
+        .latest(document)
+        .map_or(1, |latest| latest.version + 1))
+}
+
+/// Point `<document>-latest.pdf` at `file`, replacing the old link in one step.
+fn link_latest(dir: &Path, document: PublishDocument, file: &str) -> Result<PathBuf, PublishError> {
+    let link = dir.join(format!("{}-latest.pdf", document.name()));
+    let tmp = dir.join(format!(".{}-latest.tmp", document.name()));
+    let _ = fs::remove_file(&tmp);
+    std::os::unix::fs::symlink(file, &tmp).map_err(write_error(&tmp))?;
+    fs::rename(&tmp, &link).map_err(write_error(&link))?;
+    Ok(link)
+}
+
+/// Publish the next version of `document` into `dir`, rendering it with `render`, which
+/// is given the version number to show.  Nothing is published if rendering the latest
+/// version again gives the same pdf, as nothing has changed since.
+pub fn publish(
+    dir: &Path,
+    document: PublishDocument,
+    render: impl Fn(&str) -> Result<Vec<u8>, RenderError>,
+) -> Result<Option<Release>, PublishError> {
+    fs::create_dir_all(dir).map_err(write_error(dir))?;
+    let mut manifest = Manifest::load(dir)?;
+    let version = match manifest.latest(document) {
+        Some(latest) => {
+            if hash(&render(&latest.version.to_string())?) == latest.hash {
+                return Ok(None);
+            }
+            latest.version + 1
+        }
+        None => 1,
+    };
+    let pdf = render(&version.to_string())?;
+    let file = format!("{}-v{}.pdf", document.name(), version);
+    write_atomic(&dir.join(&file), &pdf)?;
+    let release = Release {
+        version,
+        file: file.clone(),
+        published: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        hash: hash(&pdf),
+    };
+    manifest
+        .documents
+        .entry(document.name().to_string())
+        .or_default()
+        .push(release.clone());
+    manifest.save(dir)?;
+    link_latest(dir, document, &file)?;
+    Ok(Some(release))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish() {
+        let dir = std::env::temp_dir().join(format!("gffd-publish-{}", std::process::id()));
+        assert_eq!(1, next_version(&dir, PublishDocument::Brochure).unwrap());
+        let content = std::cell::RefCell::new("first".to_string());
+        let render = |version: &str| Ok(format!("{} v{}", content.borrow(), version).into_bytes());
+
+        let first = publish(&dir, PublishDocument::Brochure, render)
+            .unwrap()
+            .unwrap();
+        assert_eq!(1, first.version);
+        assert_eq!("brochure-v1.pdf", first.file);
+        // Same content, so nothing new to publish
+        assert!(publish(&dir, PublishDocument::Brochure, render)
+            .unwrap()
+            .is_none());
+
+        *content.borrow_mut() = "second".to_string();
+        let second = publish(&dir, PublishDocument::Brochure, render)
+            .unwrap()
+            .unwrap();
+        assert_eq!(2, second.version);
+        assert_eq!(3, next_version(&dir, PublishDocument::Brochure).unwrap());
+        assert_eq!(
+            b"second v2".to_vec(),
+            fs::read(dir.join("brochure-latest.pdf")).unwrap()
+        );
+        assert_eq!(
+            PathBuf::from("brochure-v2.pdf"),
+            fs::read_link(dir.join("brochure-latest.pdf")).unwrap()
+        );
+        assert_eq!(
+            b"first v1".to_vec(),
+            fs::read(dir.join("brochure-v1.pdf")).unwrap()
+        );
+
+        // Documents are versioned separately
+        let summaries = publish(&dir, PublishDocument::Summaries, render)
+            .unwrap()
+            .unwrap();
+        assert_eq!(1, summaries.version);
+
+        let manifest = Manifest::load(&dir).unwrap();
+        assert_eq!(2, manifest.documents["brochure"].len());
+        assert_eq!(hash(b"second v2"), manifest.documents["brochure"][1].hash);
+        assert_eq!(
+            Some(&summaries),
+            manifest.latest(PublishDocument::Summaries)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,563 @@
+use crate::args::{BrochureFormat, PosterProfile, PublishDocument, RenderDocument, RenderOptions};
+use crate::config::Config;
+use crate::films::{fetch_image, BrochureEntry, FestivalEvent, SummaryEntry};
+use crate::posters;
+use crate::publish::{self, PublishError};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use summary::{Brochure, RenderError, Showing, Summary, SummaryFilter, TypstMap};
+
+impl RenderOptions {
+    /// The directory of replacement templates, if one was given.
+    pub fn template_dir(&self) -> Option<&Path> {
+        if self.templates.is_empty() {
+            None
+        } else {
+            Some(Path::new(&self.templates))
+        }
+    }
+
+    pub fn output_or(&self, default: &str) -> String {
+        if self.output.is_empty() {
+            default.to_string()
+        } else {
+            self.output.clone()
+        }
+    }
+}
+
+impl RenderDocument {
+    /// Without a version given, show the next one this would be published as, which is
+    /// the brochure's for the brochure and the summaries' for the rest.
+    pub fn fill_version(&mut self, cfg: &Config) -> Result<(), PublishError> {
+        let (options, document) = match self {
+            RenderDocument::Brochure { options, .. } => (options, PublishDocument::Brochure),
+            RenderDocument::Summary { options }
+            | RenderDocument::PersonSummary { options }
+            | RenderDocument::FilterSummary { options, .. } => {
+                (options, PublishDocument::Summaries)
+            }
+        };
+        if options.doc_version.is_empty() {
+            let version = if cfg.publish_directory.is_empty() {
+                1
+            } else {
+                publish::next_version(Path::new(&cfg.publish_directory), document)?
+            };
+            options.doc_version = version.to_string();
+        }
+        Ok(())
+    }
+}
+
+fn brochure_entry(entry: BrochureEntry) -> summary::BrochureEntry {
+    summary::BrochureEntry {
+        name: entry.name,
+        id: entry.id,
+        sortname: entry.sortname,
+        showings: entry
+            .showings
+            .into_iter()
+            .map(|s| summary::BrochureShowing {
+                screen: s.screen,
+                time: s.time,
+                date: s.date,
+                datetime: s.datetime,
+                changed: false,
+                badges: s.badges,
+            })
+            .collect(),
+        duration: entry.duration.into(),
+        synopsis: entry.synopsis,
+        synopsis_html: entry.synopsis_html,
+        starring: entry.starring,
+        genres: entry.genres,
+        directed_by: entry.directed_by,
+        rating: entry.rating,
+        rating_reason: entry.rating_reason,
+        strand: entry.strand,
+        colour: entry.colour,
+        poster: entry.poster,
+        poster_bytes: None,
+        year: entry.year,
+        country: entry.country,
+        language: entry.language,
+        subtitles: entry.subtitles,
+        certificate: entry.certificate,
+        trailer: entry.trailer,
+        secondary: entry.secondary,
+        ..Default::default()
+    }
+}
+
+/// One brochure entry per film, sorted for the listing.
+pub fn brochure_entries(events: &[FestivalEvent]) -> Vec<BrochureEntry> {
+    let by_film = events.iter().fold(
+        BTreeMap::new(),
+        |mut m: BTreeMap<u32, Vec<FestivalEvent>>, e| {
+            m.entry(e.movie_id).or_default().push(e.clone());
+            m
+        },
+    );
+    let mut entries = by_film
+        .iter()
+        .map(|(id, events)| BrochureEntry::from_event(*id, events))
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.sortname.cmp(&b.sortname));
+    entries
+}
+
+/// Fetch again any poster that is missing or won't decode.  Failures are only
+/// reported, the brochure shows a placeholder for them.
+fn ensure_posters(cfg: &Config, events: &[FestivalEvent]) {
+    let keys = events
+        .iter()
+        .map(|e| e.poster.as_str())
+        .filter(|k| !k.is_empty())
+        .collect::<BTreeSet<_>>();
+    for key in keys {
+        let path = format!("{}/posters/{}.jpg", &cfg.state_directory, key);
+        if fs::read(&path).is_ok_and(|data| summary::valid_poster(&data)) {
+            continue;
+        }
+        let _ = fs::remove_file(&path);
+        if let Err(e) = fetch_image(cfg, key) {
+            println!("Unable to fetch poster {} - {}", key, e);
+        }
+    }
+}
+
+/// Point each entry at a copy of its poster sized for the profile.  If that can't be
+/// made the original is used, or the placeholder if that is broken too.
+fn use_variants(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    entries: &mut [summary::BrochureEntry],
+    profile: PosterProfile,
+) {
+    let keys = events
+        .iter()
+        .map(|e| (e.movie_id.to_string(), e.poster.as_str()))
+        .collect::<HashMap<_, _>>();
+    for entry in entries {
+        let Some(key) = keys.get(&entry.id).filter(|k| !k.is_empty()) else {
+            continue;
+        };
+        match posters::variant(cfg, key, profile) {
+            Ok(path) => entry.poster = path,
+            Err(e) => println!("Unable to prepare poster for {} - {}", entry.name, e),
+        }
+    }
+}
+
+impl BrochureFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BrochureFormat::Pdf => "pdf",
+            BrochureFormat::Html => "html",
+            BrochureFormat::Epub => "epub",
+        }
+    }
+}
+
+pub fn brochure(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    options: &RenderOptions,
+    profile: PosterProfile,
+    grid: bool,
+    format: BrochureFormat,
+) -> Result<Vec<u8>, RenderError> {
+    let brochure = brochure_inputs(cfg, events, options, profile, grid);
+    match format {
+        BrochureFormat::Pdf => summary::brochure_pdf(brochure, options.template_dir()),
+        BrochureFormat::Html => Ok(summary::brochure_html(&brochure).into_bytes()),
+        BrochureFormat::Epub => summary::brochure_epub(&brochure),
+    }
+}
+
+/// Everything the brochure template needs, with posters fetched and prepared for the
+/// profile.
+pub fn brochure_inputs(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    options: &RenderOptions,
+    profile: PosterProfile,
+    grid: bool,
+) -> Brochure {
+    let mut entries = brochure_entries(events)
+        .into_iter()
+        .map(brochure_entry)
+        .collect::<Vec<_>>();
+    let changes = previous_brochure(cfg, &options.doc_version)
+        .map(|(since, previous)| summary::brochure_changes(&since, &previous, &mut entries))
+        .filter(|changes| !changes.is_empty());
+    ensure_posters(cfg, events);
+    use_variants(cfg, events, &mut entries, profile);
+    let missing = summary::load_pictures(&mut entries, Path::new(&cfg.state_directory));
+    if !missing.is_empty() {
+        println!("No artwork for {} films:", missing.len());
+        for name in &missing {
+            println!("  {}", name);
+        }
+    }
+    let mut brochure = Brochure::new(entries, &options.doc_version);
+    brochure.changes = changes;
+    if grid {
+        brochure.grid = Some(brochure_grid(cfg, events));
+    }
+    brochure
+}
+
+// The entries of each published version are kept so the next one can say what changed
+fn published_path(cfg: &Config, version: &str) -> PathBuf {
+    Path::new(&cfg.state_directory)
+        .join("published")
+        .join(format!("brochure-v{}.json", version))
+}
+
+/// The entries of the latest brochure with a lower version than `version`, along with
+/// that version.  Only numbered versions are compared.
+pub fn previous_brochure(
+    cfg: &Config,
+    version: &str,
+) -> Option<(String, Vec<summary::BrochureEntry>)> {
+    let version = version.parse::<u32>().ok()?;
+    let dir = Path::new(&cfg.state_directory).join("published");
+    let previous = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|f| f.ok())
+        .filter_map(|f| {
+            f.file_name()
+                .to_str()?
+                .strip_prefix("brochure-v")?
+                .strip_suffix(".json")?
+                .parse::<u32>()
+                .ok()
+        })
+        .filter(|v| *v < version)
+        .max()?
+        .to_string();
+    let data = fs::read(published_path(cfg, &previous)).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(entries) => Some((previous, entries)),
+        Err(e) => {
+            println!("Ignoring brochure v{} - {}", previous, e);
+            None
+        }
+    }
+}
+
+/// Keep the entries of a published version for the next one to compare with.  Only
+/// call this once the version has been published, a version that was only rendered
+/// would hide its changes from the next one.
+pub fn save_published(
+    cfg: &Config,
+    version: &str,
+    entries: &[summary::BrochureEntry],
+) -> Result<(), RenderError> {
+    let path = published_path(cfg, version);
+    let name = path.display().to_string();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|_| RenderError::WriteError(name.clone()))?;
+    }
+    let json =
+        serde_json::to_vec_pretty(entries).map_err(|_| RenderError::WriteError(name.clone()))?;
+    fs::write(&path, json).map_err(|_| RenderError::WriteError(name))
+}
+
+/// The day grids for the brochure, which is for sharing so leaves out who is going.
+pub fn brochure_grid(cfg: &Config, events: &[FestivalEvent]) -> TypstMap<TypstMap<Vec<Showing>>> {
+    let mut grid = summary_inputs(cfg, events, "").summary;
+    for screens in grid.values_mut() {
+        for showings in screens.values_mut() {
+            showings.iter_mut().for_each(|s| s.attendees.clear());
+        }
+    }
+    grid
+}
+
+/// Screenings grouped by date and then screen, with attendees by their initials.
+pub fn summary_inputs(cfg: &Config, events: &[FestivalEvent], version: &str) -> Summary {
+    let mut data: TypstMap<TypstMap<Vec<Showing>>> = TypstMap::sorted();
+    for evt in events {
+        for (date, screen, entry) in
+            SummaryEntry::from_event(evt.movie_id, std::slice::from_ref(evt))
+        {
+            let SummaryEntry {
+                start,
+                title,
+                strand,
+                duration,
+                color,
+                id,
+                day,
+                secondary,
+                badges,
+                extra_minutes,
+            } = entry;
+            data.entry(date)
+                .or_insert_with(TypstMap::sorted)
+                .entry(screen)
+                .or_default()
+                .push(Showing {
+                    start,
+                    title,
+                    strand,
+                    duration: duration.into(),
+                    color,
+                    id: Some(id),
+                    day,
+                    attendees: evt.attendees.iter().map(|a| cfg.initials_of(a)).collect(),
+                    secondary,
+                    badges,
+                    extra_minutes: extra_minutes.into(),
+                });
+        }
+    }
+    for screens in data.values_mut() {
+        for showings in screens.values_mut() {
+            showings.sort_by(|a, b| a.start.cmp(&b.start));
+        }
+    }
+    let colours = cfg
+        .strands
+        .iter()
+        .map(|(k, v)| (k.clone(), v.colour.clone()))
+        .collect::<std::collections::HashMap<_, _>>()
+        .into();
+    let names = cfg.names.clone().into();
+    Summary {
+        version: version.to_string(),
+        summary: data,
+        colours,
+        names,
+        filters: vec![],
+        full: true,
+    }
+}
+
+pub fn summary(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    options: &RenderOptions,
+) -> Result<Vec<u8>, RenderError> {
+    summary::summary_pdf(
+        summary_inputs(cfg, events, &options.doc_version),
+        options.template_dir(),
+    )
+}
+
+pub fn person_summary(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    options: &RenderOptions,
+) -> Result<Vec<u8>, RenderError> {
+    summary::person_summary_pdf(
+        summary_inputs(cfg, events, &options.doc_version),
+        options.template_dir(),
+    )
+}
+
+/// The people and groups to filter for, all of them if none are named.
+pub fn summary_filters(cfg: &Config, people: &[String]) -> Result<Vec<SummaryFilter>, RenderError> {
+    let groups = cfg.summary_groups();
+    let filters = groups
+        .iter()
+        .filter(|(title, _)| {
+            people.is_empty() || people.iter().any(|p| p.eq_ignore_ascii_case(title))
+        })
+        .map(|(title, initials)| SummaryFilter {
+            title: title.clone(),
+            people: initials.clone(),
+        })
+        .collect::<Vec<_>>();
+    if let Some(missing) = people
+        .iter()
+        .find(|p| !groups.keys().any(|t| t.eq_ignore_ascii_case(p)))
+    {
+        return Err(RenderError::UnknownFilter(missing.clone()));
+    }
+    Ok(filters)
+}
+
+/// The full grids followed by the filtered grids for each person and group.
+pub fn filter_summary(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    options: &RenderOptions,
+    people: &[String],
+) -> Result<Vec<u8>, RenderError> {
+    let mut inputs = summary_inputs(cfg, events, &options.doc_version);
+    inputs.filters = summary_filters(cfg, people)?;
+    summary::filter_summary_pdf(inputs, options.template_dir())
+}
+
+/// One pdf per person and group, containing only their filtered grids.
+pub fn filter_summaries(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    options: &RenderOptions,
+    people: &[String],
+) -> Result<Vec<(String, Vec<u8>)>, RenderError> {
+    summary_filters(cfg, people)?
+        .into_iter()
+        .map(|filter| {
+            let mut inputs = summary_inputs(cfg, events, &options.doc_version);
+            inputs.full = false;
+            let title = filter.title.clone();
+            inputs.filters = vec![filter];
+            summary::filter_summary_pdf(inputs, options.template_dir()).map(|pdf| (title, pdf))
+        })
+        .collect()
+}
+
+/// `filter-summary.pdf` becomes `filter-summary-marion-and-neil.pdf`.
+pub fn person_path(output: &str, title: &str) -> PathBuf {
+    let path = Path::new(output);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let slug = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, slug, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, slug),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn event(movie_id: u32, hour: u32, attendees: &[&str]) -> FestivalEvent {
+        FestivalEvent {
+            movie_id,
+            date: NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            start: NaiveTime::from_hms_opt(hour, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(hour + 1, 30, 0).unwrap(),
+            screen: "GFT 1".to_string(),
+            attendees: attendees.iter().map(|e| e.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summary_inputs() {
+        let mut cfg = Config::default();
+        cfg.names.insert("M".to_string(), "Marion".to_string());
+        let events = vec![event(2, 18, &[]), event(1, 13, &["Marion", "Neil"])];
+        let inputs = summary_inputs(&cfg, &events, "3");
+        let showings = &inputs.summary["2026-02-25"]["GFT 1"];
+        assert_eq!(2, showings.len());
+        assert_eq!("13:00", showings[0].start);
+        assert_eq!(90, showings[0].duration);
+        assert_eq!(vec!["M", "N"], showings[0].attendees);
+        assert_eq!("3", inputs.version);
+    }
+
+    #[test]
+    fn test_brochure_grid() {
+        let events = vec![event(1, 13, &["Marion", "Neil"])];
+        let grid = brochure_grid(&Config::default(), &events);
+        let showings = &grid["2026-02-25"]["GFT 1"];
+        assert_eq!(Some("1".to_string()), showings[0].id);
+        assert!(showings[0].attendees.is_empty());
+    }
+
+    #[test]
+    fn test_previous_brochure() {
+        let mut cfg = Config::default();
+        let dir = std::env::temp_dir().join(format!("gffd-published-{}", std::process::id()));
+        cfg.state_directory = dir.display().to_string();
+        let entries = |ids: &[u32]| {
+            let events = ids.iter().map(|id| event(*id, 13, &[])).collect::<Vec<_>>();
+            brochure_entries(&events)
+                .into_iter()
+                .map(brochure_entry)
+                .collect::<Vec<_>>()
+        };
+        assert!(previous_brochure(&cfg, "2").is_none());
+        save_published(&cfg, "1", &entries(&[1])).unwrap();
+        save_published(&cfg, "2", &entries(&[1, 2])).unwrap();
+        save_published(&cfg, "draft", &entries(&[3])).unwrap();
+
+        let (since, previous) = previous_brochure(&cfg, "3").unwrap();
+        assert_eq!("2", since);
+        assert_eq!(2, previous.len());
+        // A re-render of a version compares with the one before, not itself
+        assert_eq!("1", previous_brochure(&cfg, "2").unwrap().0);
+        assert!(previous_brochure(&cfg, "draft").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fill_version() {
+        let options = |version: &str| RenderOptions {
+            output: "".to_string(),
+            doc_version: version.to_string(),
+            templates: "".to_string(),
+        };
+        let version = |document: &RenderDocument| match document {
+            RenderDocument::Brochure { options, .. } | RenderDocument::Summary { options } => {
+                options.doc_version.clone()
+            }
+            _ => unreachable!(),
+        };
+        let mut cfg = Config::default();
+        let mut summary = RenderDocument::Summary {
+            options: options(""),
+        };
+        summary.fill_version(&cfg).unwrap();
+        assert_eq!("1", version(&summary));
+
+        let dir = std::env::temp_dir().join(format!("gffd-next-{}", std::process::id()));
+        cfg.publish_directory = dir.display().to_string();
+        publish::publish(&dir, PublishDocument::Brochure, |_| Ok(vec![])).unwrap();
+        let brochure = |version: &str| RenderDocument::Brochure {
+            options: options(version),
+            profile: PosterProfile::Print,
+            grid: false,
+            format: BrochureFormat::Pdf,
+        };
+        let mut next = brochure("");
+        next.fill_version(&cfg).unwrap();
+        assert_eq!("2", version(&next));
+        // A version given is kept
+        let mut given = brochure("draft");
+        given.fill_version(&cfg).unwrap();
+        assert_eq!("draft", version(&given));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_summary_filters() {
+        let mut cfg = Config::default();
+        cfg.names.insert("M".to_string(), "Marion".to_string());
+        cfg.names.insert("N".to_string(), "Neil".to_string());
+        cfg.groups.insert(
+            "Marion and Neil".to_string(),
+            vec!["M".to_string(), "Neil".to_string()],
+        );
+        let filters = summary_filters(&cfg, &[]).unwrap();
+        let titles = filters.iter().map(|f| f.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["Marion", "Marion and Neil", "Neil"], titles);
+        assert_eq!(vec!["M", "N"], filters[1].people);
+        let filters = summary_filters(&cfg, &["neil".to_string()]).unwrap();
+        assert_eq!(1, filters.len());
+        assert!(summary_filters(&cfg, &["Nobody".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_person_path() {
+        assert_eq!(
+            PathBuf::from("out/filter-summary-marion-and-neil.pdf"),
+            person_path("out/filter-summary.pdf", "Marion and Neil")
+        );
+    }
+}
@@ -0,0 +1,651 @@
+use crate::calendar::{fill_attendance, set_attendance, CalendarError};
+use crate::config::Config;
+use crate::films::FestivalEvent;
+use crate::ics::calendar;
+use chrono::NaiveDate;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{header, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use percent_encoding::percent_decode_str;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::net::TcpListener;
+
+#[derive(Error, Debug)]
+pub enum ServerError {
+    // Unable to bind the listening socket
+    #[error("Unable to listen on port {0} - {1}")]
+    BindError(u16, String),
+    // Unable to load the festival data
+    #[error("Unable to load the festival data - {0}")]
+    LoadError(String),
+    // Google Calendar couldn't be reached or refused a request
+    #[error(transparent)]
+    Calendar(#[from] CalendarError),
+}
+
+impl ServerError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ServerError::Calendar(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+// The grid covers 10:00 to 24:00, as summary.typ does
+const GRID_START: i64 = 10 * 60;
+const GRID_MINUTES: i64 = 14 * 60;
+
+pub async fn serve(cfg: Config) -> Result<(), ServerError> {
+    let port = cfg.server_options.port;
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| ServerError::BindError(port, format!("{}", e)))?;
+    println!("gffd: listening on {}", addr);
+    let cfg = Arc::new(cfg);
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let cfg = cfg.clone();
+        tokio::task::spawn(async move {
+            let service = service_fn(move |req| route(req, cfg.clone()));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                println!("gffd: connection error - {}", e);
+            }
+        });
+    }
+}
+
+async fn route(
+    req: Request<Incoming>,
+    cfg: Arc<Config>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = req.uri().path().to_owned();
+    let query = query_params(req.uri().query().unwrap_or(""));
+    let person = query.get("person").cloned().unwrap_or_default();
+    let token = query.get("token").cloned().unwrap_or_default();
+    let response = match (req.method(), path.as_str()) {
+        (&Method::GET, "/") => match load_events(&cfg).await {
+            Ok(events) => html(index_page(&cfg, &events, &person, &token)),
+            Err(e) => error(e.status(), &format!("{}", e)),
+        },
+        (&Method::GET, p) if p.starts_with("/day/") => {
+            match NaiveDate::parse_from_str(&p[5..], "%Y-%m-%d") {
+                Ok(date) => match load_events(&cfg).await {
+                    Ok(events) => html(day_page(&cfg, &events, date, &person, &token)),
+                    Err(e) => error(e.status(), &format!("{}", e)),
+                },
+                Err(_) => error(StatusCode::NOT_FOUND, "No such day"),
+            }
+        }
+        (&Method::GET, p) if p.starts_with("/ics/") && p.ends_with(".ics") => {
+            let feed = decode(&p[5..p.len() - 4]);
+            ics_feed(&cfg, &feed, &token).await
+        }
+        (&Method::POST, "/attend") => {
+            let body = match req.into_body().collect().await {
+                Ok(b) => b.to_bytes(),
+                Err(e) => return Ok(error(StatusCode::BAD_REQUEST, &format!("{}", e))),
+            };
+            let form = query_params(&String::from_utf8_lossy(&body));
+            attend(&cfg, &form).await
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    };
+    Ok(response)
+}
+
+async fn attend(cfg: &Config, form: &HashMap<String, String>) -> Response<Full<Bytes>> {
+    let screening_id = form
+        .get("screening_id")
+        .and_then(|e| e.parse::<u32>().ok())
+        .unwrap_or(0);
+    let person = form.get("person").cloned().unwrap_or_default();
+    let token = form.get("token").cloned().unwrap_or_default();
+    let date = form
+        .get("date")
+        .and_then(|e| NaiveDate::parse_from_str(e, "%Y-%m-%d").ok());
+    let going = form.get("going").map(|e| e == "true").unwrap_or(false);
+    if screening_id == 0 || !cfg.names.values().any(|e| *e == person) {
+        return error(StatusCode::BAD_REQUEST, "Unknown screening or person");
+    }
+    if !may_attend(cfg, &person, &token) {
+        return error(StatusCode::FORBIDDEN, "Unknown token");
+    }
+    // Checked before the calendar changes, as it goes into the redirect
+    let Some(date) = date else {
+        return error(StatusCode::BAD_REQUEST, "Unknown date");
+    };
+    match set_attendance(cfg, screening_id, &person, going).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return error(
+                StatusCode::NOT_FOUND,
+                "Screening is not on the main calendar",
+            );
+        }
+        Err(e) => return error(StatusCode::BAD_GATEWAY, &format!("{}", e)),
+    }
+    redirect(&format!(
+        "/day/{}?{}",
+        date.format("%Y-%m-%d"),
+        person_query(&person, &token)
+    ))
+}
+
+/// Whether `token` is the person's own feed token, which is also needed to change what
+/// they are going to.
+fn may_attend(cfg: &Config, person: &str, token: &str) -> bool {
+    !token.is_empty()
+        && cfg
+            .server_options
+            .feed_tokens
+            .get(person)
+            .is_some_and(|e| same_token(e, token))
+}
+
+/// Serve `/ics/<person>.ics` or `/ics/all.ics`.  A person's feed needs their own token,
+/// the full feed accepts anyone's.
+async fn ics_feed(cfg: &Arc<Config>, feed: &str, token: &str) -> Response<Full<Bytes>> {
+    let tokens = &cfg.server_options.feed_tokens;
+    let allowed = !token.is_empty()
+        && if feed == "all" {
+            // Every token is checked, so the time taken doesn't say which one matched
+            tokens
+                .values()
+                .fold(false, |found, e| same_token(e, token) | found)
+        } else {
+            tokens.get(feed).is_some_and(|e| same_token(e, token))
+        };
+    if !allowed {
+        return error(StatusCode::FORBIDDEN, "Unknown feed or token");
+    }
+    let events = match load_events(cfg).await {
+        Ok(events) => events,
+        Err(e) => return error(e.status(), &format!("{}", e)),
+    };
+    let mut selected = events
+        .iter()
+        .filter(|e| feed == "all" || e.attendees.iter().any(|a| a == feed))
+        .collect::<Vec<_>>();
+    selected.sort_by_key(|e| (e.date, e.start, e.screening_id));
+    let name = if feed == "all" {
+        "GFF 2026".to_string()
+    } else {
+        format!("GFF 2026 - {}", feed)
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+        .body(Full::new(Bytes::from(calendar(&name, &selected))))
+        .unwrap()
+}
+
+/// Read every cached screening and fill in the attendees from the main calendar.
+async fn load_events(cfg: &Arc<Config>) -> Result<Vec<FestivalEvent>, ServerError> {
+    let blocking_cfg = cfg.clone();
+    let mut events = tokio::task::spawn_blocking(move || FestivalEvent::fetch_all(&blocking_cfg))
+        .await
+        .map_err(|e| ServerError::LoadError(format!("{}", e)))?
+        .map_err(|e| ServerError::LoadError(format!("{}", e)))?;
+    fill_attendance(cfg, &mut events).await?;
+    Ok(events)
+}
+
+fn query_params(query: &str) -> HashMap<String, String> {
+    form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+fn encode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+// A path segment, where + is a plus rather than a space as in a query
+fn decode(segment: &str) -> String {
+    percent_decode_str(segment).decode_utf8_lossy().into_owned()
+}
+
+// Looks at every byte whatever the first difference, so a token can't be guessed a
+// byte at a time from how long a wrong one takes to be turned away
+fn same_token(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn html(body: String) -> Response<Full<Bytes>> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Full::new(Bytes::from(body)))
+        .unwrap()
+}
+
+fn redirect(location: &str) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(StatusCode::SEE_OTHER)
+        .header(header::LOCATION, location)
+        .body(Full::new(Bytes::new()))
+        .unwrap()
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    let mut response = html(page(
+        "Error",
+        &format!("<h1>{}</h1><p>{}</p>", status, escape(message)),
+    ));
+    *response.status_mut() = status;
+    response
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><meta name="viewport" content="width=device-width, initial-scale=1">
+<title>GFF 2026 - {}</title>
+<style>
+body {{ font-family: "Source Serif 4", serif; margin: 1em; }}
+.grid {{ position: relative; border: 1px solid black; }}
+.hours, .screen {{ position: relative; height: 64px; border-top: 1px solid #ccc; }}
+.hours {{ height: 1.5em; border-top: none; }}
+.hour {{ position: absolute; top: 0; font-size: 0.8em; border-left: 1px solid black; padding-left: 2px; height: 100%; }}
+.name {{ position: absolute; left: 0; width: 10%; padding: 2px; font-size: 0.9em; overflow: hidden; }}
+.film {{ position: absolute; top: 2px; height: 58px; box-sizing: border-box; border: 1px solid black; border-radius: 3px; padding: 2px; font-size: 0.75em; overflow: hidden; }}
+.film a {{ color: black; }}
+.film form {{ display: inline; }}
+.marker {{ display: inline-block; width: 0.6em; height: 0.6em; border-radius: 50%; border: 1px solid black; margin-right: 2px; }}
+.going {{ font-weight: bold; }}
+.extra {{ position: absolute; top: 2px; height: 58px; box-sizing: border-box; border: 1px dashed black; border-left: none; border-radius: 0 3px 3px 0; }}
+.clash {{ border: 2px solid red; }}
+</style></head>
+<body>{}</body></html>"#,
+        escape(title),
+        body
+    )
+}
+
+// The query for a person's pages, keeping their token so they can still mark attendance
+fn person_query(person: &str, token: &str) -> String {
+    if token.is_empty() {
+        format!("person={}", encode(person))
+    } else {
+        format!("person={}&token={}", encode(person), encode(token))
+    }
+}
+
+fn people_links(cfg: &Config, target: &str, person: &str, token: &str) -> String {
+    let mut names = cfg.names.values().collect::<Vec<_>>();
+    names.sort();
+    let links = names
+        .iter()
+        .map(|name| {
+            let label = if *name == person {
+                format!("<b>{}</b>", escape(name))
+            } else {
+                escape(name)
+            };
+            // Only the person's own link keeps the token
+            let token = if *name == person { token } else { "" };
+            format!(
+                "<a href=\"{}?{}\">{}</a>",
+                target,
+                escape(&person_query(name, token)),
+                label
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
+    format!("<p>Marking attendance for: {}</p>", links)
+}
+
+fn index_page(cfg: &Config, events: &[FestivalEvent], person: &str, token: &str) -> String {
+    let days = events.iter().fold(BTreeMap::new(), |mut m, e| {
+        *m.entry(e.date).or_insert(0) += 1;
+        m
+    });
+    let list = days
+        .iter()
+        .map(|(date, count)| {
+            format!(
+                "<li><a href=\"/day/{}?{}\">{}</a> ({} screenings)</li>",
+                date.format("%Y-%m-%d"),
+                escape(&person_query(person, token)),
+                date.format("%A, %B %-d"),
+                count
+            )
+        })
+        .collect::<String>();
+    page(
+        "Summary",
+        &format!(
+            "<h1>GFF 2026</h1>{}<ul>{}</ul>",
+            people_links(cfg, "/", person, token),
+            list
+        ),
+    )
+}
+
+/// Render one day as the summary grid - a row per screen, blocks placed by start time
+/// and sized by duration, coloured by strand.  Time allowed for a Q&A trails the film
+/// in outline, and the person's screenings that overlap once it is allowed for are
+/// outlined in red.  Attendance can only be changed with the person's token.
+fn day_page(
+    cfg: &Config,
+    events: &[FestivalEvent],
+    date: NaiveDate,
+    person: &str,
+    token: &str,
+) -> String {
+    let token = if may_attend(cfg, person, token) {
+        token
+    } else {
+        ""
+    };
+    let by_screen = events.iter().filter(|e| e.date == date).fold(
+        BTreeMap::new(),
+        |mut m: BTreeMap<&str, Vec<&FestivalEvent>>, e| {
+            m.entry(&e.screen).or_default().push(e);
+            m
+        },
+    );
+    // Everything the person is going to, including the days either side for late finishes
+    let going = events
+        .iter()
+        .filter(|e| !person.is_empty() && e.attendees.iter().any(|a| a == person))
+        .collect::<Vec<_>>();
+    let clashes = |evt: &FestivalEvent| {
+        going.iter().any(|g| g.screening_id == evt.screening_id)
+            && going
+                .iter()
+                .any(|g| g.screening_id != evt.screening_id && g.clashes_with(evt))
+    };
+    let hours = (0..14)
+        .map(|i| {
+            format!(
+                "<div class=\"hour\" style=\"left: {}\">{}</div>",
+                offset(i * 60),
+                10 + i
+            )
+        })
+        .collect::<String>();
+    let rows = by_screen
+        .iter()
+        .map(|(screen, films)| {
+            let blocks = films
+                .iter()
+                .map(|e| film_block(e, date, person, token, clashes(e)))
+                .collect::<String>();
+            format!(
+                "<div class=\"screen\"><div class=\"name\">{}</div>{}</div>",
+                escape(screen),
+                blocks
+            )
+        })
+        .collect::<String>();
+    let day = format!("{}", date.format("%Y-%m-%d"));
+    page(
+        &day,
+        &format!(
+            "<h1>{} - {}</h1><p><a href=\"/?{}\">All days</a></p>{}<div class=\"grid\"><div class=\"hours\">{}</div>{}</div>",
+            day,
+            date.format("%A"),
+            escape(&person_query(person, token)),
+            people_links(cfg, &format!("/day/{}", day), person, token),
+            hours,
+            rows
+        ),
+    )
+}
+
+/// `token` is empty unless the person may change their attendance.
+fn film_block(
+    evt: &FestivalEvent,
+    date: NaiveDate,
+    person: &str,
+    token: &str,
+    clash: bool,
+) -> String {
+    let start = (evt.start - chrono::NaiveTime::MIN).num_minutes();
+    let duration = i64::from(evt.duration());
+    let extra = i64::from(evt.extra_minutes);
+    let going = evt.attendees.iter().any(|e| e == person);
+    let toggle = if token.is_empty() {
+        "".to_string()
+    } else {
+        format!(
+            r#"<form method="post" action="/attend"><input type="hidden" name="screening_id" value="{}"><input type="hidden" name="person" value="{}"><input type="hidden" name="token" value="{}"><input type="hidden" name="date" value="{}"><input type="hidden" name="going" value="{}"><label><input type="checkbox" onchange="this.form.submit()"{}> going</label></form>"#,
+            evt.screening_id,
+            escape(person),
+            escape(token),
+            date.format("%Y-%m-%d"),
+            !going,
+            if going { " checked" } else { "" }
+        )
+    };
+    // A dot for each other strand the film is in
+    let markers = evt
+        .secondary_strands()
+        .iter()
+        .map(|s| {
+            format!(
+                "<span class=\"marker\" style=\"background: #{}\" title=\"{}\"></span>",
+                escape(&s.colour),
+                escape(&s.name)
+            )
+        })
+        .collect::<String>();
+    let badges = evt
+        .badges
+        .iter()
+        .map(|b| {
+            format!(
+                " <abbr title=\"{}\">{}</abbr>",
+                escape(&b.name),
+                escape(&b.symbol)
+            )
+        })
+        .collect::<String>();
+    let tail = if extra == 0 {
+        "".to_string()
+    } else {
+        format!(
+            "<div class=\"extra\" style=\"left: {}; width: {}\"></div>",
+            offset(start + duration - GRID_START),
+            percent(extra)
+        )
+    };
+    format!(
+        "{}<div class=\"film{}{}\" style=\"left: {}; width: {}; background: #{}50\" title=\"{} {} ({})\">{}{} {}{}<br>{}<br>{}</div>",
+        tail,
+        if going { " going" } else { "" },
+        if clash { " clash" } else { "" },
+        offset(start - GRID_START),
+        percent(duration),
+        escape(&evt.strand_colour),
+        evt.start.format("%H:%M"),
+        escape(&evt.title),
+        evt.running_time(),
+        markers,
+        evt.start.format("%H:%M"),
+        escape(&evt.title),
+        badges,
+        escape(&evt.attendees.join(", ")),
+        toggle
+    )
+}
+
+/// Horizontal position of a point `minutes` after 10:00, leaving 10% for the screen names.
+fn offset(minutes: i64) -> String {
+    format!(
+        "calc(10% + {:.3}%)",
+        minutes as f64 * 90.0 / GRID_MINUTES as f64
+    )
+}
+
+fn percent(minutes: i64) -> String {
+    format!("{:.3}%", minutes as f64 * 90.0 / GRID_MINUTES as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            "Tom &amp; Jerry &lt;b&gt; &quot;x&quot;",
+            escape("Tom & Jerry <b> \"x\"")
+        );
+    }
+
+    #[test]
+    fn test_grid_positions() {
+        assert_eq!("calc(10% + 0.000%)", offset(0));
+        assert_eq!("calc(10% + 45.000%)", offset(7 * 60));
+        assert_eq!("90.000%", percent(GRID_MINUTES));
+    }
+
+    fn attend_config() -> Config {
+        let mut cfg = Config::default();
+        cfg.names.insert("N".to_string(), "Neil".to_string());
+        cfg.server_options
+            .feed_tokens
+            .insert("Neil".to_string(), "secret".to_string());
+        cfg.calendar_auth_file = "/nonexistent/google_auth.json".to_string();
+        cfg
+    }
+
+    #[tokio::test]
+    async fn test_attend_date() {
+        let cfg = attend_config();
+        let form = HashMap::from([
+            ("screening_id".to_string(), "12".to_string()),
+            ("person".to_string(), "Neil".to_string()),
+            ("token".to_string(), "secret".to_string()),
+            (
+                "date".to_string(),
+                "2026-02-25\nSet-Cookie: x=1".to_string(),
+            ),
+        ]);
+        assert_eq!(StatusCode::BAD_REQUEST, attend(&cfg, &form).await.status());
+    }
+
+    #[tokio::test]
+    async fn test_attend_token() {
+        let cfg = attend_config();
+        for token in ["", "secrex"] {
+            let form = HashMap::from([
+                ("screening_id".to_string(), "12".to_string()),
+                ("person".to_string(), "Neil".to_string()),
+                ("token".to_string(), token.to_string()),
+                ("date".to_string(), "2026-02-25".to_string()),
+                ("going".to_string(), "true".to_string()),
+            ]);
+            assert_eq!(StatusCode::FORBIDDEN, attend(&cfg, &form).await.status());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calendar_errors() {
+        let cfg = attend_config();
+        let form = HashMap::from([
+            ("screening_id".to_string(), "12".to_string()),
+            ("person".to_string(), "Neil".to_string()),
+            ("token".to_string(), "secret".to_string()),
+            ("date".to_string(), "2026-02-25".to_string()),
+            ("going".to_string(), "true".to_string()),
+        ]);
+        let response = attend(&cfg, &form).await;
+        assert_eq!(StatusCode::BAD_GATEWAY, response.status());
+        let error = ServerError::from(CalendarError::Auth(
+            "google_auth.json".to_string(),
+            "not found".to_string(),
+        ));
+        assert_eq!(StatusCode::BAD_GATEWAY, error.status());
+    }
+
+    #[test]
+    fn test_film_block() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 25).unwrap();
+        let evt = FestivalEvent {
+            date,
+            start: chrono::NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            title: "Hamnet".to_string(),
+            extra_minutes: 30,
+            ..Default::default()
+        };
+        let block = film_block(&evt, date, "Neil", "secret", true);
+        assert!(block.starts_with(&format!(
+            "<div class=\"extra\" style=\"left: {}; width: {}\"></div>",
+            offset(10 * 60),
+            percent(30)
+        )));
+        assert!(block.contains("class=\"film clash\""));
+        assert!(block.contains(&format!("width: {};", percent(120))));
+        assert!(block.contains("title=\"18:00 Hamnet (120 min + 30 min after)\""));
+        assert!(block.contains("<input type=\"hidden\" name=\"token\" value=\"secret\">"));
+        // Without the token there's nothing to change attendance with
+        assert!(!film_block(&evt, date, "Neil", "", true).contains("<form"));
+
+        let cfg = attend_config();
+        let day = day_page(&cfg, std::slice::from_ref(&evt), date, "Neil", "secrex");
+        assert!(!day.contains("<form"));
+        let day = day_page(&cfg, &[evt], date, "Neil", "secret");
+        assert!(day.contains("<form"));
+        assert!(day.contains("href=\"/?person=Neil&amp;token=secret\""));
+    }
+
+    #[test]
+    fn test_query_params() {
+        let params = query_params("person=Neil+D&screening_id=12&going=true");
+        assert_eq!("Neil D", params.get("person").unwrap());
+        assert_eq!("12", params.get("screening_id").unwrap());
+        assert_eq!("Neil+D", encode("Neil D"));
+        assert_eq!("Neil D", decode("Neil%20D"));
+        assert_eq!("Neil+D", decode("Neil+D"));
+    }
+
+    #[tokio::test]
+    async fn test_feed_tokens() {
+        assert!(same_token("secret", "secret"));
+        assert!(!same_token("secret", "secrex"));
+        assert!(!same_token("secret", "secret2"));
+
+        let mut cfg = Config::default();
+        cfg.server_options
+            .feed_tokens
+            .insert("Neil D".to_string(), "secret".to_string());
+        let cfg = Arc::new(cfg);
+        for (feed, token) in [("Neil D", "secrex"), ("Neil D", ""), ("all", "secre")] {
+            assert_eq!(
+                StatusCode::FORBIDDEN,
+                ics_feed(&cfg, feed, token).await.status()
+            );
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::export::ExportError;
+use crate::films::{BrochureEntry, FestivalEvent};
+use chrono::{NaiveDate, NaiveTime};
+use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet, XlsxError};
+use std::collections::{BTreeMap, BTreeSet};
+
+fn to_error(e: XlsxError) -> ExportError {
+    ExportError::WriteError("xlsx".to_string(), format!("{}", e))
+}
+
+/// A strand colour lightened the way summary.typ does it, with an alpha of 0x50
+/// over white, so that black text stays readable.
+fn tint(colour: &str) -> Color {
+    let rgb = u32::from_str_radix(colour.trim_start_matches('#'), 16).unwrap_or(0xffffff);
+    let blend = |c: u32| 255 - ((255 - c) * 0x50) / 255;
+    Color::RGB(
+        (blend((rgb >> 16) & 0xff) << 16) | (blend((rgb >> 8) & 0xff) << 8) | blend(rgb & 0xff),
+    )
+}
+
+/// A workbook with one sheet per day, screens across and start times down, and a
+/// sheet listing every film.
+pub fn workbook(events: &[&FestivalEvent]) -> Result<Vec<u8>, ExportError> {
+    let mut workbook = Workbook::new();
+    let by_day = events.iter().fold(
+        BTreeMap::new(),
+        |mut m: BTreeMap<NaiveDate, Vec<&FestivalEvent>>, e| {
+            m.entry(e.date).or_default().push(e);
+            m
+        },
+    );
+    for (date, screenings) in &by_day {
+        let sheet = workbook.add_worksheet();
+        day_sheet(sheet, *date, screenings).map_err(to_error)?;
+    }
+    let sheet = workbook.add_worksheet();
+    films_sheet(sheet, events).map_err(to_error)?;
+    workbook.save_to_buffer().map_err(to_error)
+}
+
+fn header_format() -> Format {
+    Format::new()
+        .set_bold()
+        .set_border(FormatBorder::Thin)
+        .set_background_color(Color::RGB(0xd9d9d9))
+}
+
+fn day_sheet(
+    sheet: &mut Worksheet,
+    date: NaiveDate,
+    screenings: &[&FestivalEvent],
+) -> Result<(), XlsxError> {
+    sheet.set_name(date.format("%a %-d %b").to_string())?;
+    let screens = screenings
+        .iter()
+        .map(|e| e.screen.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let times = screenings
+        .iter()
+        .map(|e| e.start)
+        .collect::<BTreeSet<NaiveTime>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let header = header_format();
+    sheet.write_string_with_format(0, 0, date.format("%A %-d %B").to_string(), &header)?;
+    sheet.set_column_width(0, 8)?;
+    for (col, screen) in screens.iter().enumerate() {
+        let col = col as u16 + 1;
+        sheet.write_string_with_format(0, col, *screen, &header)?;
+        sheet.set_column_width(col, 28)?;
+    }
+    for (row, time) in times.iter().enumerate() {
+        let row = row as u32 + 1;
+        sheet.write_string_with_format(row, 0, time.format("%H:%M").to_string(), &header)?;
+    }
+    for evt in screenings {
+        let row = times.iter().position(|t| *t == evt.start).unwrap_or(0) as u32 + 1;
+        let col = screens.iter().position(|s| *s == evt.screen).unwrap_or(0) as u16 + 1;
+        let format = Format::new()
+            .set_text_wrap()
+            .set_align(FormatAlign::Top)
+            .set_border(FormatBorder::Thin)
+            .set_background_color(tint(&evt.strand_colour));
+        let text = format!(
+            "{}\n{}, ends {}\n{}",
+            evt.title,
+            evt.running_time(),
+            evt.adjusted_end().format("%H:%M"),
+            evt.strand
+        );
+        sheet.write_string_with_format(row, col, text.trim_end(), &format)?;
+    }
+    sheet.set_freeze_panes(1, 1)?;
+    Ok(())
+}
+
+fn films_sheet(sheet: &mut Worksheet, events: &[&FestivalEvent]) -> Result<(), XlsxError> {
+    sheet.set_name("Films")?;
+    let by_film = events.iter().fold(
+        BTreeMap::new(),
+        |mut m: BTreeMap<u32, Vec<FestivalEvent>>, e| {
+            m.entry(e.movie_id).or_default().push((*e).clone());
+            m
+        },
+    );
+    let mut films = by_film
+        .iter()
+        .map(|(id, events)| (BrochureEntry::from_event(*id, events), &events[0]))
+        .collect::<Vec<_>>();
+    films.sort_by(|a, b| a.0.sortname.cmp(&b.0.sortname));
+
+    let header = header_format();
+    let headers = [
+        ("Title", 30),
+        ("Strand", 20),
+        ("Directed By", 20),
+        ("Genres", 20),
+        ("Duration (min)", 8),
+        ("Rating", 8),
+        ("Starring", 30),
+        ("Showings", 30),
+        ("Made", 16),
+        ("Language", 16),
+        ("Certificate", 16),
+        ("Trailer", 30),
+        ("Synopsis", 80),
+    ];
+    for (col, (name, width)) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &header)?;
+        sheet.set_column_width(col as u16, *width)?;
+    }
+    let wrap = Format::new().set_text_wrap().set_align(FormatAlign::Top);
+    for (row, (film, first)) in films.iter().enumerate() {
+        let row = row as u32 + 1;
+        let strand = wrap
+            .clone()
+            .set_background_color(tint(&first.strand_colour));
+        let showings = by_film[&first.movie_id]
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} {} - {}",
+                    e.date.format("%a %-d %b"),
+                    e.start.format("%H:%M"),
+                    e.screen
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        sheet.write_string_with_format(row, 0, &film.name, &strand)?;
+        sheet.write_string_with_format(row, 1, &film.strand, &strand)?;
+        sheet.write_string_with_format(row, 2, &film.directed_by, &wrap)?;
+        sheet.write_string_with_format(row, 3, &film.genres, &wrap)?;
+        sheet.write_number_with_format(row, 4, film.duration, &wrap)?;
+        sheet.write_string_with_format(row, 5, &film.rating, &wrap)?;
+        sheet.write_string_with_format(row, 6, &film.starring, &wrap)?;
+        sheet.write_string_with_format(row, 7, &showings, &wrap)?;
+        sheet.write_string_with_format(row, 8, first.made(), &wrap)?;
+        sheet.write_string_with_format(row, 9, first.spoken(), &wrap)?;
+        sheet.write_string_with_format(row, 10, &film.certificate, &wrap)?;
+        if film.trailer.is_empty() {
+            sheet.write_blank(row, 11, &wrap)?;
+        } else {
+            sheet.write_url(row, 11, film.trailer.as_str())?;
+        }
+        sheet.write_string_with_format(row, 12, first.plain_synopsis(), &wrap)?;
+    }
+    sheet.set_freeze_panes(1, 1)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tint() {
+        assert_eq!(Color::RGB(0xffffff), tint("ffffff"));
+        assert_eq!(Color::RGB(0xafafaf), tint("000000"));
+    }
+
+    #[test]
+    fn test_workbook() {
+        let evt = FestivalEvent {
+            date: NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            start: NaiveTime::from_hms_opt(13, 10, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(14, 18, 0).unwrap(),
+            title: "I Am Martin Parr".to_string(),
+            screen: "GFT 1".to_string(),
+            strand_colour: "6596d0".to_string(),
+            ..Default::default()
+        };
+        let data = workbook(&[&evt]).unwrap();
+        assert_eq!(b"PK", &data[0..2]);
+    }
+}
@@ -4,7 +4,7 @@ pkgs.stdenv.mkDerivation rec {
   version = "0.1.2";
   src = ./..;
   nativeBuildInputs = with pkgs; [ makeWrapper ];
-  buildInputs = with pkgs; [ jq coreutils curl typst bash openssh ];
+  buildInputs = with pkgs; [ jq coreutils curl bash openssh ];
   dontUnpack = true;
   dontPatch = true;
   dontConfigure = true;
@@ -16,7 +16,7 @@ pkgs.stdenv.mkDerivation rec {
     cp $src/brochure/* $out/brochure
   '';
   postFixup = ''
-    for script in $out/bin/*.sh $out/bin/gff-* ; do
+    for script in $out/bin/*.sh ; do
       wrapProgram $script --set PATH '${lib.makeBinPath buildInputs}'
     done
   '';
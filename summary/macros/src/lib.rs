@@ -0,0 +1,160 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, LitStr};
+
+/// Derive `summary::IntoTypst` along with typst's `IntoValue`, and `Dict` conversion for
+/// structs so they can be template inputs.
+///
+/// Structs with named fields become dictionaries keyed by field name.  Enums must have
+/// only unit variants and become strings, with the variant name in kebab case.  Both
+/// accept `#[typst(rename = "name")]`, and fields accept `#[typst(skip)]`.
+#[proc_macro_derive(IntoTypst, attributes(typst))]
+pub fn derive_into_typst(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    rename: Option<String>,
+    skip: bool,
+}
+
+fn options(attrs: &[Attribute]) -> syn::Result<Options> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("typst")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename` or `skip`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn kebab_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('-');
+            }
+            out.extend(c.to_lowercase());
+        } else if c == '_' {
+            out.push('-');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    // Every type parameter is converted, so each needs to be IntoTypst itself
+    let mut generics = input.generics.clone();
+    for param in input.generics.type_params() {
+        let ident = &param.ident;
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#ident: ::summary::IntoTypst));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let foundations = quote!(::summary::__private::typst::foundations);
+
+    let (body, dict) = match &input.data {
+        Data::Struct(data) => {
+            let Fields::Named(fields) = &data.fields else {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "IntoTypst needs a struct with named fields",
+                ));
+            };
+            let mut inserts = vec![];
+            for field in &fields.named {
+                let options = options(&field.attrs)?;
+                if options.skip {
+                    continue;
+                }
+                let ident = field.ident.as_ref().unwrap();
+                let key = options
+                    .rename
+                    .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+                inserts.push(quote! {
+                    dict.insert(#key.into(), ::summary::IntoTypst::into_typst(self.#ident));
+                });
+            }
+            let body = quote! {
+                #[allow(unused_mut)]
+                let mut dict = #foundations::Dict::new();
+                #(#inserts)*
+                #foundations::Value::Dict(dict)
+            };
+            let dict = quote! {
+                impl #impl_generics ::core::convert::From<#name #ty_generics> for #foundations::Dict #where_clause {
+                    fn from(value: #name #ty_generics) -> Self {
+                        match ::summary::IntoTypst::into_typst(value) {
+                            #foundations::Value::Dict(dict) => dict,
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+            };
+            (body, dict)
+        }
+        Data::Enum(data) => {
+            let mut arms = vec![];
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "IntoTypst only supports enums with unit variants",
+                    ));
+                }
+                let options = options(&variant.attrs)?;
+                let ident = &variant.ident;
+                let value = options
+                    .rename
+                    .unwrap_or_else(|| kebab_case(&ident.to_string()));
+                arms.push(quote! { #name::#ident => #value });
+            }
+            let body = quote! {
+                let name: &str = match self { #(#arms,)* };
+                #foundations::Value::Str(name.into())
+            };
+            (body, quote!())
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "IntoTypst doesn't support unions",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::summary::IntoTypst for #name #ty_generics #where_clause {
+            fn into_typst(self) -> #foundations::Value {
+                #body
+            }
+        }
+
+        impl #impl_generics #foundations::IntoValue for #name #ty_generics #where_clause {
+            fn into_value(self) -> #foundations::Value {
+                ::summary::IntoTypst::into_typst(self)
+            }
+        }
+
+        #dict
+    })
+}
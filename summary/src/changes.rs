@@ -0,0 +1,199 @@
+use crate::{BrochureEntry, BrochureShowing, IndexFilm};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use typst_bake::IntoValue;
+
+/// What differs from the brochure published as `since`.
+#[derive(Clone, Debug, Default, PartialEq, IntoValue, Serialize, Deserialize)]
+pub struct BrochureChanges {
+    pub since: String,
+    pub new_films: Vec<IndexFilm>,
+    // Extra showings of films that were already in the brochure
+    pub added: Vec<ShowingChange>,
+    pub cancelled: Vec<ShowingChange>,
+    pub moved: Vec<ShowingChange>,
+}
+
+/// A showing that changed.  `was` is empty for an added showing and `now` for a
+/// cancelled one.
+#[derive(Clone, Debug, PartialEq, IntoValue, Serialize, Deserialize)]
+pub struct ShowingChange {
+    pub name: String,
+    pub id: String,
+    pub was: String,
+    pub now: String,
+}
+
+impl BrochureChanges {
+    pub fn is_empty(&self) -> bool {
+        self.new_films.is_empty()
+            && self.added.is_empty()
+            && self.cancelled.is_empty()
+            && self.moved.is_empty()
+    }
+}
+
+fn describe(showing: &BrochureShowing) -> String {
+    format!("{} {}, {}", showing.date, showing.time, showing.screen)
+}
+
+fn same(a: &BrochureShowing, b: &BrochureShowing) -> bool {
+    a.datetime == b.datetime && a.screen == b.screen
+}
+
+/// Compare `current` with the `previous` brochure, marking each showing that is new or
+/// has moved as changed.  Showings are matched on time and screen, and whatever is
+/// left over on both sides of a film is paired up in time order as moves.
+pub fn brochure_changes(
+    since: &str,
+    previous: &[BrochureEntry],
+    current: &mut [BrochureEntry],
+) -> BrochureChanges {
+    let mut changes = BrochureChanges {
+        since: since.to_string(),
+        ..Default::default()
+    };
+    let mut before = previous
+        .iter()
+        .map(|e| (e.id.as_str(), e))
+        .collect::<HashMap<_, _>>();
+    for entry in current.iter_mut() {
+        let Some(old) = before.remove(entry.id.as_str()) else {
+            changes.new_films.push(IndexFilm {
+                name: entry.name.clone(),
+                id: entry.id.clone(),
+            });
+            entry.showings.iter_mut().for_each(|s| s.changed = true);
+            continue;
+        };
+        let mut gone = old
+            .showings
+            .iter()
+            .filter(|o| !entry.showings.iter().any(|s| same(o, s)))
+            .collect::<Vec<_>>();
+        gone.sort_by(|a, b| a.datetime.cmp(&b.datetime));
+        let mut gone = gone.into_iter();
+        let mut fresh = entry
+            .showings
+            .iter_mut()
+            .filter(|s| !old.showings.iter().any(|o| same(o, s)))
+            .collect::<Vec<_>>();
+        fresh.sort_by(|a, b| a.datetime.cmp(&b.datetime));
+        for showing in fresh {
+            showing.changed = true;
+            let change = |was: String| ShowingChange {
+                name: entry.name.clone(),
+                id: entry.id.clone(),
+                was,
+                now: describe(showing),
+            };
+            match gone.next() {
+                Some(o) => changes.moved.push(change(describe(o))),
+                None => changes.added.push(change("".to_string())),
+            }
+        }
+        for o in gone {
+            changes.cancelled.push(ShowingChange {
+                name: entry.name.clone(),
+                id: entry.id.clone(),
+                was: describe(o),
+                now: "".to_string(),
+            });
+        }
+    }
+    // Films dropped altogether, in the order they were listed
+    for old in previous
+        .iter()
+        .filter(|e| before.contains_key(e.id.as_str()))
+    {
+        for o in &old.showings {
+            changes.cancelled.push(ShowingChange {
+                name: old.name.clone(),
+                id: "".to_string(),
+                was: describe(o),
+                now: "".to_string(),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn showing(datetime: &str, screen: &str) -> BrochureShowing {
+        BrochureShowing {
+            screen: screen.to_string(),
+            time: datetime[11..16].to_string(),
+            date: datetime[0..10].to_string(),
+            datetime: format!("{}:00Z", datetime),
+            ..Default::default()
+        }
+    }
+
+    fn entry(id: &str, showings: Vec<BrochureShowing>) -> BrochureEntry {
+        BrochureEntry {
+            id: id.to_string(),
+            showings,
+            ..crate::tests::entry(&format!("Film {}", id), "")
+        }
+    }
+
+    #[test]
+    fn test_changes() {
+        let previous = vec![
+            entry(
+                "1",
+                vec![
+                    showing("2026-02-25T13:00", "GFT 1"),
+                    showing("2026-02-26T18:00", "GFT 2"),
+                ],
+            ),
+            entry("2", vec![showing("2026-02-27T20:30", "GFT 1")]),
+            entry("3", vec![showing("2026-02-28T11:00", "GFT 3")]),
+        ];
+        let mut current = vec![
+            entry(
+                "1",
+                vec![
+                    showing("2026-02-25T13:00", "GFT 1"),
+                    showing("2026-02-26T18:00", "Odeon 10"),
+                ],
+            ),
+            entry(
+                "2",
+                vec![
+                    showing("2026-02-27T20:30", "GFT 1"),
+                    showing("2026-03-01T15:00", "GFT 1"),
+                ],
+            ),
+            entry("4", vec![showing("2026-03-01T18:00", "GFT 2")]),
+        ];
+        let changes = brochure_changes("7", &previous, &mut current);
+        assert_eq!("7", changes.since);
+        assert_eq!(
+            vec!["4"],
+            changes.new_films.iter().map(|f| &f.id).collect::<Vec<_>>()
+        );
+        assert_eq!(1, changes.moved.len());
+        assert_eq!("2026-02-26 18:00, GFT 2", changes.moved[0].was);
+        assert_eq!("2026-02-26 18:00, Odeon 10", changes.moved[0].now);
+        assert_eq!(1, changes.added.len());
+        assert_eq!("2026-03-01 15:00, GFT 1", changes.added[0].now);
+        assert_eq!(1, changes.cancelled.len());
+        assert_eq!("Film 3", changes.cancelled[0].name);
+
+        let marked = current
+            .iter()
+            .map(|e| e.showings.iter().map(|s| s.changed).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![vec![false, true], vec![false, true], vec![true]],
+            marked
+        );
+
+        let mut again = current.clone();
+        assert!(brochure_changes("8", &current, &mut again).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::TypstMap;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap};
+use typst::foundations::{Array, Datetime, Dict, IntoValue, Value};
+
+/// Conversion into a typst value, for types typst doesn't know about.  Derive it with
+/// `#[derive(IntoTypst)]` to pass a struct or unit enum to a template.
+pub trait IntoTypst {
+    fn into_typst(self) -> Value;
+}
+
+macro_rules! into_typst_via_value {
+    ($($t:ty),*) => {
+        $(impl IntoTypst for $t {
+            fn into_typst(self) -> Value {
+                self.into_value()
+            }
+        })*
+    };
+}
+
+into_typst_via_value!(bool, i8, i16, i32, i64, u8, u16, u32, f64, String, &str, Value, Dict, Array);
+
+impl IntoTypst for f32 {
+    fn into_typst(self) -> Value {
+        Value::Float(self.into())
+    }
+}
+
+// typst integers are i64, so larger values are clamped rather than wrapped
+impl IntoTypst for u64 {
+    fn into_typst(self) -> Value {
+        Value::Int(self.min(i64::MAX as u64) as i64)
+    }
+}
+
+impl IntoTypst for usize {
+    fn into_typst(self) -> Value {
+        (self as u64).into_typst()
+    }
+}
+
+impl<T: IntoTypst> IntoTypst for Option<T> {
+    fn into_typst(self) -> Value {
+        match self {
+            Some(v) => v.into_typst(),
+            None => Value::None,
+        }
+    }
+}
+
+impl<T: IntoTypst> IntoTypst for Vec<T> {
+    fn into_typst(self) -> Value {
+        Value::Array(self.into_iter().map(IntoTypst::into_typst).collect())
+    }
+}
+
+fn dict<K: Into<String>, T: IntoTypst>(entries: impl Iterator<Item = (K, T)>) -> Value {
+    let mut dict = Dict::new();
+    for (k, v) in entries {
+        dict.insert(k.into().into(), v.into_typst());
+    }
+    Value::Dict(dict)
+}
+
+// Sorted, since a HashMap's own order changes from run to run
+impl<K: Into<String> + Ord, T: IntoTypst> IntoTypst for HashMap<K, T> {
+    fn into_typst(self) -> Value {
+        let mut entries = self.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        dict(entries.into_iter())
+    }
+}
+
+impl<K: Into<String>, T: IntoTypst> IntoTypst for BTreeMap<K, T> {
+    fn into_typst(self) -> Value {
+        dict(self.into_iter())
+    }
+}
+
+impl<K: Into<String>, T: IntoTypst> IntoTypst for IndexMap<K, T> {
+    fn into_typst(self) -> Value {
+        dict(self.into_iter())
+    }
+}
+
+impl<T: typst::foundations::IntoValue> IntoTypst for TypstMap<T> {
+    fn into_typst(self) -> Value {
+        self.into_value()
+    }
+}
+
+// Dates typst can't hold, such as years past 9999, are passed on as text instead
+impl IntoTypst for NaiveDate {
+    fn into_typst(self) -> Value {
+        Datetime::from_ymd(self.year(), self.month() as u8, self.day() as u8)
+            .map(Value::Datetime)
+            .unwrap_or_else(|| Value::Str(self.to_string().into()))
+    }
+}
+
+impl IntoTypst for NaiveTime {
+    fn into_typst(self) -> Value {
+        Datetime::from_hms(self.hour() as u8, self.minute() as u8, self.second() as u8)
+            .map(Value::Datetime)
+            .unwrap_or_else(|| Value::Str(self.to_string().into()))
+    }
+}
+
+impl IntoTypst for NaiveDateTime {
+    fn into_typst(self) -> Value {
+        Datetime::from_ymd_hms(
+            self.year(),
+            self.month() as u8,
+            self.day() as u8,
+            self.hour() as u8,
+            self.minute() as u8,
+            self.second() as u8,
+        )
+        .map(Value::Datetime)
+        .unwrap_or_else(|| Value::Str(self.to_string().into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntoTypst;
+
+    #[derive(IntoTypst, Clone, Copy)]
+    enum Kind {
+        Gala,
+        #[allow(dead_code)]
+        FrightFest,
+        #[typst(rename = "cs")]
+        #[allow(dead_code)]
+        CinemaSundays,
+    }
+
+    #[derive(IntoTypst)]
+    struct Event {
+        title: String,
+        date: NaiveDate,
+        start: NaiveTime,
+        kind: Kind,
+        #[typst(rename = "people")]
+        attendees: BTreeMap<String, Vec<String>>,
+        extra: Option<HashMap<String, u32>>,
+        #[typst(skip)]
+        #[allow(dead_code)]
+        hidden: u32,
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(
+            Value::Str("fright-fest".into()),
+            Kind::FrightFest.into_typst()
+        );
+        assert_eq!(Value::Str("cs".into()), Kind::CinemaSundays.into_typst());
+        assert_eq!(Value::None, None::<u32>.into_typst());
+        assert_eq!(Value::Int(i64::MAX), u64::MAX.into_typst());
+        assert_eq!(
+            Value::Datetime(Datetime::from_ymd(2026, 2, 25).unwrap()),
+            NaiveDate::from_ymd_opt(2026, 2, 25).unwrap().into_typst()
+        );
+        assert_eq!(
+            Value::Str("+12026-02-25".into()),
+            NaiveDate::from_ymd_opt(12026, 2, 25).unwrap().into_typst()
+        );
+        assert_eq!(
+            Value::Str("+12026-02-25 18:30:00".into()),
+            NaiveDate::from_ymd_opt(12026, 2, 25)
+                .unwrap()
+                .and_hms_opt(18, 30, 0)
+                .unwrap()
+                .into_typst()
+        );
+    }
+
+    #[derive(IntoTypst)]
+    struct Pair<T> {
+        first: T,
+        second: Option<T>,
+    }
+
+    #[test]
+    fn test_derive() {
+        let event = Event {
+            title: "Hamnet".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 2, 25).unwrap(),
+            start: NaiveTime::from_hms_opt(18, 30, 0).unwrap(),
+            kind: Kind::Gala,
+            attendees: BTreeMap::from([("M".to_string(), vec!["Marion".to_string()])]),
+            extra: Some(HashMap::from([("b".to_string(), 2), ("a".to_string(), 1)])),
+            hidden: 3,
+        };
+        let dict = Dict::from(event);
+        let keys = dict.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["title", "date", "start", "kind", "people", "extra"],
+            keys
+        );
+        assert_eq!(
+            &Value::Datetime(Datetime::from_hms(18, 30, 0).unwrap()),
+            dict.get("start").unwrap()
+        );
+        assert_eq!(&Value::Str("gala".into()), dict.get("kind").unwrap());
+        let Value::Dict(extra) = dict.get("extra").unwrap() else {
+            panic!("extra isn't a dictionary");
+        };
+        let keys = extra.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b"], keys);
+
+        let pair = Dict::from(Pair {
+            first: Kind::Gala,
+            second: None,
+        });
+        assert_eq!(&Value::Str("gala".into()), pair.get("first").unwrap());
+        assert_eq!(&Value::None, pair.get("second").unwrap());
+    }
+}
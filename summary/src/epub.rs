@@ -0,0 +1,144 @@
+use crate::html::{anchor, escape, film_html, image_type, STYLE};
+use crate::{Brochure, RenderError};
+use chrono::Utc;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" \
+         xml:lang=\"en-GB\" lang=\"en-GB\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        body
+    )
+}
+
+fn extension(mime: &str) -> &str {
+    mime.trim_start_matches("image/")
+}
+
+/// The brochure as an EPUB 3 book: one page of films, with a contents entry and
+/// anchor per film.
+pub fn brochure_epub(brochure: &Brochure) -> Result<Vec<u8>, RenderError> {
+    let title = format!("GFF 2026 v{}", brochure.version);
+    let mut posters = vec![];
+    let mut films = format!("<h1>{}</h1>\n", escape(&title));
+    let mut toc = String::new();
+    for entry in &brochure.entries {
+        let poster = entry.poster_bytes.as_ref().and_then(|data| {
+            let mime = image_type(data)?;
+            let file = format!("posters/{}.{}", entry.id, extension(mime));
+            posters.push((file.clone(), mime, data));
+            Some(file)
+        });
+        films.push_str(&film_html(entry, poster));
+        toc.push_str(&format!(
+            "<li><a href=\"brochure.xhtml#{}\">{}</a></li>\n",
+            anchor(entry),
+            escape(&entry.name)
+        ));
+    }
+    let nav = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>Films</h1>\n<ol>\n{}</ol>\n</nav>\n",
+        toc
+    );
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"brochure\" href=\"brochure.xhtml\" media-type=\"application/xhtml+xml\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    for (i, (file, mime, _)) in posters.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"poster{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            i, file, mime
+        ));
+    }
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:identifier id=\"id\">urn:gff:2026:brochure:v{}</dc:identifier>\n\
+         <dc:title>{}</dc:title>\n<dc:language>en-GB</dc:language>\n\
+         <meta property=\"dcterms:modified\">{}</meta>\n</metadata>\n\
+         <manifest>\n{}</manifest>\n<spine>\n<itemref idref=\"brochure\"/>\n</spine>\n</package>\n",
+        escape(&brochure.version),
+        escape(&title),
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest
+    );
+
+    let error = |e: &dyn std::fmt::Display| RenderError::WriteError(format!("epub - {}", e));
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    // The mimetype has to come first, uncompressed
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut files: Vec<(String, SimpleFileOptions, &[u8])> = vec![
+        ("mimetype".to_string(), stored, b"application/epub+zip"),
+        (
+            "META-INF/container.xml".to_string(),
+            deflated,
+            CONTAINER.as_bytes(),
+        ),
+    ];
+    let opf = opf.into_bytes();
+    let nav = xhtml(&title, &nav).into_bytes();
+    let page = xhtml(&title, &films).into_bytes();
+    files.push(("OEBPS/content.opf".to_string(), deflated, &opf));
+    files.push(("OEBPS/nav.xhtml".to_string(), deflated, &nav));
+    files.push(("OEBPS/brochure.xhtml".to_string(), deflated, &page));
+    files.push(("OEBPS/style.css".to_string(), deflated, STYLE.as_bytes()));
+    for (file, _, data) in &posters {
+        files.push((format!("OEBPS/{}", file), stored, data));
+    }
+    for (name, options, data) in files {
+        zip.start_file(name, options).map_err(|e| error(&e))?;
+        zip.write_all(data).map_err(|e| error(&e))?;
+    }
+    Ok(zip.finish().map_err(|e| error(&e))?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_brochure_epub() {
+        let mut entry = crate::tests::entry("Fargo", "");
+        entry.id = "12".to_string();
+        entry.poster_bytes = Some(b"\x89PNG not really".to_vec());
+        let epub = brochure_epub(&Brochure::new(vec![entry], "4")).unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(epub)).unwrap();
+        let names = archive.file_names().map(String::from).collect::<Vec<_>>();
+        assert!(names.contains(&"OEBPS/posters/12.png".to_string()));
+        let mut mimetype = archive.by_index(0).unwrap();
+        assert_eq!("mimetype", mimetype.name());
+        assert_eq!(CompressionMethod::Stored, mimetype.compression());
+        let mut text = String::new();
+        mimetype.read_to_string(&mut text).unwrap();
+        assert_eq!("application/epub+zip", text);
+        drop(mimetype);
+
+        let mut page = String::new();
+        archive
+            .by_name("OEBPS/brochure.xhtml")
+            .unwrap()
+            .read_to_string(&mut page)
+            .unwrap();
+        assert!(page.contains("id=\"film-12\""));
+        assert!(page.contains("src=\"posters/12.png\""));
+    }
+}
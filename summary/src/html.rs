@@ -0,0 +1,287 @@
+use crate::{Brochure, BrochureEntry, RichText};
+use base64::Engine;
+use std::collections::BTreeMap;
+
+// Shared by the html page and the epub, which has no script so ignores the controls
+pub(crate) const STYLE: &str = r#"
+body { font-family: "Source Serif 4", Georgia, serif; margin: 0 auto; max-width: 40em; padding: 0 0.75em; line-height: 1.4; }
+header { position: sticky; top: 0; background: white; padding: 0.5em 0; border-bottom: 1px solid #ccc; }
+header h1 { font-size: 1.2em; margin: 0 0 0.4em; }
+#search { width: 100%; box-sizing: border-box; font-size: 1em; padding: 0.4em; }
+.chips { display: flex; flex-wrap: wrap; gap: 0.3em; margin-top: 0.4em; }
+.chip { border: 1px solid #333; border-radius: 1em; padding: 0.1em 0.6em; font-size: 0.85em; background: #eee; }
+.chips button.on { outline: 2px solid black; font-weight: bold; }
+abbr.badge { font-weight: bold; text-decoration: none; }
+.chip.secondary { font-size: 0.75em; border-style: dashed; }
+article.film { border-bottom: 1px solid #ccc; padding: 0.75em 0; overflow: hidden; }
+article.film h2 { font-size: 1.1em; margin: 0 0 0.3em; }
+article.film h2 a { color: inherit; text-decoration: none; }
+.rating { font-weight: normal; color: #555; }
+img.poster { float: right; width: 6em; margin: 0 0 0.5em 0.75em; }
+ul.showings { padding-left: 1.2em; margin: 0.3em 0; font-size: 0.9em; }
+dl.details { font-size: 0.85em; margin: 0.3em 0; }
+dl.details dt { font-weight: bold; }
+dl.details dd { margin: 0 0 0.2em 0; }
+.synopsis { font-size: 0.95em; }
+"#;
+
+const SCRIPT: &str = r#"
+const search = document.getElementById("search");
+const chips = [...document.querySelectorAll(".chips button")];
+let strand = "";
+function update() {
+  const query = search.value.trim().toLowerCase();
+  for (const film of document.querySelectorAll("article.film")) {
+    film.hidden = (strand !== "" && !film.dataset.strands.split("|").includes(strand))
+      || (query !== "" && !film.dataset.search.includes(query));
+  }
+  chips.forEach(c => c.classList.toggle("on", c.dataset.strand === strand));
+}
+search.addEventListener("input", update);
+chips.forEach(c => c.addEventListener("click", () => {
+  strand = c.dataset.strand === strand ? "" : c.dataset.strand;
+  update();
+}));
+update();
+"#;
+
+/// Escape text for html and xhtml, including inside attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The mime type of a poster, if it's an image format browsers and readers show.
+pub(crate) fn image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG") {
+        Some("image/png")
+    } else if data.len() > 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+pub(crate) fn anchor(entry: &BrochureEntry) -> String {
+    format!("film-{}", entry.id)
+}
+
+/// One film as an article, with `poster` giving the image source, if any.
+pub(crate) fn film_html(entry: &BrochureEntry, poster: Option<String>) -> String {
+    let search = [
+        &entry.name,
+        &entry.strand,
+        &entry.directed_by,
+        &entry.starring,
+        &entry.genres,
+        &entry.country,
+        &entry.language,
+    ]
+    .into_iter()
+    // So searching for captioned finds the films with a captioned screening
+    .chain(
+        entry
+            .showings
+            .iter()
+            .flat_map(|s| s.badges.iter().map(|b| &b.name)),
+    )
+    .map(|s| s.to_lowercase())
+    .collect::<Vec<_>>()
+    .join(" ");
+    let strands = entry.strands();
+    // | can't be in a strand name from config.json, it'd be read as a separator
+    let names = strands.iter().map(|s| s.0).collect::<Vec<_>>().join("|");
+    let mut html = format!(
+        "<article class=\"film\" id=\"{}\" data-strands=\"{}\" data-search=\"{}\">\n",
+        anchor(entry),
+        escape(&names),
+        escape(&search)
+    );
+    html.push_str(&format!(
+        "<h2><a href=\"#{}\">{}</a> <span class=\"rating\">({})</span></h2>\n",
+        anchor(entry),
+        escape(&entry.name),
+        escape(&entry.rating)
+    ));
+    if !strands.is_empty() {
+        html.push_str("<p>");
+        for (i, (name, colour)) in strands.iter().enumerate() {
+            html.push_str(&format!(
+                "<span class=\"chip{}\" style=\"background: #{}50\">{}</span> ",
+                if i == 0 { "" } else { " secondary" },
+                escape(colour),
+                escape(name)
+            ));
+        }
+        html.push_str("</p>\n");
+    }
+    if let Some(src) = poster {
+        html.push_str(&format!(
+            "<img class=\"poster\" src=\"{}\" alt=\"Poster for {}\"/>\n",
+            src,
+            escape(&entry.name)
+        ));
+    }
+    html.push_str("<ul class=\"showings\">\n");
+    for s in &entry.showings {
+        let badges = s
+            .badges
+            .iter()
+            .map(|b| {
+                format!(
+                    " <abbr class=\"badge\" title=\"{}\">{}</abbr>",
+                    escape(&b.name),
+                    escape(&b.symbol)
+                )
+            })
+            .collect::<String>();
+        html.push_str(&format!(
+            "<li>{}, {} - {}{}</li>\n",
+            escape(&s.date),
+            escape(&s.time),
+            escape(&s.screen),
+            badges
+        ));
+    }
+    html.push_str("</ul>\n");
+    let made = entry.made();
+    let spoken = entry.spoken();
+    let details = [
+        ("Starring", &entry.starring),
+        ("Directed By", &entry.directed_by),
+        ("Genres", &entry.genres),
+        ("Rating notes", &entry.rating_reason),
+        ("Made", &made),
+        ("Language", &spoken),
+        ("Certificate", &entry.certificate),
+    ];
+    if details.iter().any(|(_, v)| !v.is_empty()) {
+        html.push_str("<dl class=\"details\">\n");
+        for (name, value) in details.iter().filter(|(_, v)| !v.is_empty()) {
+            html.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", name, escape(value)));
+        }
+        html.push_str("</dl>\n");
+    }
+    if !entry.trailer.is_empty() {
+        html.push_str(&format!(
+            "<p><a class=\"trailer\" href=\"{}\">Watch the trailer</a></p>\n",
+            escape(&entry.trailer)
+        ));
+    }
+    html.push_str(&format!(
+        "<div class=\"synopsis\">{}</div>\n</article>\n",
+        RichText::parse(&entry.synopsis_html).to_html()
+    ));
+    html
+}
+
+/// A self-contained page with the posters inlined, a search box and a chip per strand
+/// to narrow the list down.
+pub fn brochure_html(brochure: &Brochure) -> String {
+    let title = format!("GFF 2026 v{}", brochure.version);
+    let strands = brochure
+        .entries
+        .iter()
+        .flat_map(|e| e.strands())
+        .collect::<BTreeMap<_, _>>();
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en-GB\">\n<head>\n<meta charset=\"utf-8\"/>\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"/>\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<header>\n<h1>{}</h1>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search films, people and genres\"/>\n\
+         <div class=\"chips\">\n<button class=\"chip\" data-strand=\"\">All</button>\n",
+        escape(&title),
+        STYLE,
+        escape(&title)
+    );
+    for (strand, colour) in &strands {
+        html.push_str(&format!(
+            "<button class=\"chip\" data-strand=\"{}\" style=\"background: #{}50\">{}</button>\n",
+            escape(strand),
+            escape(colour),
+            escape(strand)
+        ));
+    }
+    html.push_str("</div>\n</header>\n<main>\n");
+    for entry in &brochure.entries {
+        let poster = entry.poster_bytes.as_ref().and_then(|data| {
+            image_type(data).map(|mime| {
+                format!(
+                    "data:{};base64,{}",
+                    mime,
+                    base64::engine::general_purpose::STANDARD.encode(data)
+                )
+            })
+        });
+        html.push_str(&film_html(entry, poster));
+    }
+    html.push_str(&format!(
+        "</main>\n<script>{}</script>\n</body>\n</html>\n",
+        SCRIPT
+    ));
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brochure_html() {
+        let mut entry = crate::tests::entry("Fargo & Friends", "");
+        entry.id = "12".to_string();
+        entry.strand = "Coen Brothers".to_string();
+        entry.secondary = vec![crate::StrandMarker {
+            name: "Gala".to_string(),
+            colour: "f2c170".to_string(),
+        }];
+        entry.synopsis_html = "A <i>very</i> cold <b>case</b>".to_string();
+        entry.year = "1996".to_string();
+        entry.country = "USA".to_string();
+        entry.trailer = "https://www.youtube.com/watch?v=h2tY82z3xXU".to_string();
+        entry.poster_bytes = Some(vec![0xff, 0xd8, 0xff, 0xe0, 0, 0]);
+        entry.showings.push(crate::BrochureShowing {
+            screen: "GFT 1".to_string(),
+            time: "13:00".to_string(),
+            date: "Wed, February 25".to_string(),
+            datetime: "2026-02-25T13:00:00Z".to_string(),
+            changed: false,
+            badges: vec![crate::ScreeningBadge {
+                name: "Captioned".to_string(),
+                symbol: "CC".to_string(),
+            }],
+        });
+        let html = brochure_html(&Brochure::new(vec![entry], "4"));
+        assert!(html.contains("<title>GFF 2026 v4</title>"));
+        assert!(html.contains("<input id=\"search\""));
+        assert!(html.contains("<button class=\"chip\" data-strand=\"Coen Brothers\""));
+        assert!(html.contains("<button class=\"chip\" data-strand=\"Gala\""));
+        assert!(html.contains("data-strands=\"Coen Brothers|Gala\""));
+        assert!(html.contains(
+            "<li>Wed, February 25, 13:00 - GFT 1 <abbr class=\"badge\" title=\"Captioned\">CC</abbr></li>"
+        ));
+        assert!(html.contains(" captioned\""));
+        assert!(html.contains(
+            "<span class=\"chip secondary\" style=\"background: #f2c17050\">Gala</span>"
+        ));
+        assert!(html.contains("id=\"film-12\""));
+        assert!(html.contains("<a href=\"#film-12\">Fargo &amp; Friends</a>"));
+        assert!(html.contains("src=\"data:image/jpeg;base64,/9j/4AAA\""));
+        assert!(html.contains("<p>A <em>very</em> cold <strong>case</strong></p>"));
+        assert!(html.contains("<dt>Made</dt><dd>1996, USA</dd>"));
+        assert!(!html.contains("<dt>Language</dt>"));
+        assert!(html.contains("href=\"https://www.youtube.com/watch?v=h2tY82z3xXU\""));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::BrochureEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use typst_bake::IntoValue;
+
+/// A back-matter index, such as films by director.
+#[derive(Clone, Debug, PartialEq, IntoValue, Serialize, Deserialize)]
+pub struct BrochureIndex {
+    pub title: String,
+    pub groups: Vec<IndexGroup>,
+}
+
+/// The films under one heading of an index, in sortname order.
+#[derive(Clone, Debug, PartialEq, IntoValue, Serialize, Deserialize)]
+pub struct IndexGroup {
+    pub name: String,
+    // Only strands have a colour, empty otherwise
+    pub colour: String,
+    pub films: Vec<IndexFilm>,
+}
+
+/// A film in an index, `id` being the label of its brochure entry.
+#[derive(Clone, Debug, PartialEq, IntoValue, Serialize, Deserialize)]
+pub struct IndexFilm {
+    pub name: String,
+    pub id: String,
+}
+
+// Directors and genres come as comma separated lists
+fn split(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn index<'a>(
+    title: &str,
+    entries: &'a [BrochureEntry],
+    keys: impl Fn(&'a BrochureEntry) -> Vec<(&'a str, &'a str)>,
+) -> BrochureIndex {
+    let mut groups: BTreeMap<&str, (&str, Vec<&BrochureEntry>)> = BTreeMap::new();
+    for entry in entries {
+        for (name, colour) in keys(entry) {
+            let group = groups.entry(name).or_insert((colour, vec![]));
+            if !group.1.iter().any(|e| e.id == entry.id) {
+                group.1.push(entry);
+            }
+        }
+    }
+    BrochureIndex {
+        title: title.to_string(),
+        groups: groups
+            .into_iter()
+            .map(|(name, (colour, mut films))| {
+                films.sort_by(|a, b| a.sortname.cmp(&b.sortname));
+                IndexGroup {
+                    name: name.to_string(),
+                    colour: colour.to_string(),
+                    films: films
+                        .into_iter()
+                        .map(|f| IndexFilm {
+                            name: f.name.clone(),
+                            id: f.id.clone(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Indexes of the films by strand, director and genre, a film being listed under each
+/// of its strands.  Films without a strand, director or genre are left out of that
+/// index rather than grouped under a blank.
+pub fn brochure_indexes(entries: &[BrochureEntry]) -> Vec<BrochureIndex> {
+    vec![
+        index("Films by strand", entries, BrochureEntry::strands),
+        index("Films by director", entries, |e| {
+            split(&e.directed_by).map(|d| (d, "")).collect()
+        }),
+        index("Films by genre", entries, |e| {
+            split(&e.genres).map(|g| (g, "")).collect()
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, name: &str, strand: &str, directors: &str, genres: &str) -> BrochureEntry {
+        BrochureEntry {
+            id: id.to_string(),
+            sortname: name.trim_start_matches("The ").to_string(),
+            genres: genres.to_string(),
+            directed_by: directors.to_string(),
+            strand: strand.to_string(),
+            colour: if strand.is_empty() { "" } else { "a30053" }.to_string(),
+            ..crate::tests::entry(name, "")
+        }
+    }
+
+    fn names(group: &IndexGroup) -> Vec<&str> {
+        group.films.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_indexes() {
+        let mut entries = vec![
+            entry(
+                "1",
+                "The Shining",
+                "FrightFest",
+                "Stanley Kubrick",
+                "Horror",
+            ),
+            entry("2", "Fargo", "", "Joel Coen, Ethan Coen", "Crime, Comedy"),
+            entry(
+                "3",
+                "Barton Fink",
+                "",
+                "Joel Coen, Ethan Coen",
+                "Comedy, Drama",
+            ),
+            entry("4", "Hamnet", "Galas", "", ""),
+        ];
+        entries[0].secondary = vec![crate::StrandMarker {
+            name: "Galas".to_string(),
+            colour: "f2c170".to_string(),
+        }];
+        let indexes = brochure_indexes(&entries);
+        let titles = indexes.iter().map(|i| i.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            vec!["Films by strand", "Films by director", "Films by genre"],
+            titles
+        );
+
+        let strands = &indexes[0].groups;
+        assert_eq!(2, strands.len());
+        assert_eq!("FrightFest", strands[0].name);
+        assert_eq!("a30053", strands[0].colour);
+        assert_eq!("Galas", strands[1].name);
+        assert_eq!(vec!["Hamnet", "The Shining"], names(&strands[1]));
+
+        let directors = &indexes[1].groups;
+        let heads = directors
+            .iter()
+            .map(|g| g.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Ethan Coen", "Joel Coen", "Stanley Kubrick"], heads);
+        assert_eq!(vec!["Barton Fink", "Fargo"], names(&directors[1]));
+        assert_eq!("", directors[1].colour);
+
+        let genres = &indexes[2].groups;
+        assert_eq!("Comedy", genres[0].name);
+        assert_eq!(vec!["Barton Fink", "Fargo"], names(&genres[0]));
+        assert_eq!("3", genres[0].films[0].id);
+    }
+}
@@ -0,0 +1,469 @@
+// Lets IntoTypst derives inside this crate refer to it as ::summary
+extern crate self as summary;
+
+mod changes;
+mod convert;
+mod epub;
+mod html;
+mod indexes;
+mod rich_text;
+mod templates;
+mod typst_map;
+pub use changes::{brochure_changes, BrochureChanges, ShowingChange};
+pub use convert::IntoTypst;
+pub use epub::brochure_epub;
+pub use html::brochure_html;
+pub use indexes::{brochure_indexes, BrochureIndex, IndexFilm, IndexGroup};
+pub use rich_text::{Block, Inline, RichText};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+pub use summary_macros::IntoTypst;
+pub use templates::Diagnostic;
+use thiserror::Error;
+use typst::foundations::Bytes;
+pub use typst::foundations::{Dict, Value};
+use typst::visualize::{ExchangeFormat, RasterImage};
+pub use typst_bake::{IntoDict, IntoValue};
+pub use typst_map::{Order, TypstMap};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use typst;
+}
+
+#[derive(Error, Debug)]
+pub enum RenderError {
+    // Typst failed to render a template
+    #[error("Unable to render {0} - {1}")]
+    Typst(String, String),
+    // Errors in a template, one per line as file:line: message
+    #[error("{}", .0.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n"))]
+    Compile(Vec<Diagnostic>),
+    // Unable to write the output
+    #[error("Unable to write {0}")]
+    WriteError(String),
+    // A filtered summary was asked for someone who isn't in names or groups
+    #[error("No person or group called {0}")]
+    UnknownFilter(String),
+}
+
+#[derive(IntoValue, IntoDict, Serialize, Deserialize)]
+pub struct Brochure {
+    pub entries: Vec<BrochureEntry>,
+    pub version: String,
+    // Back-matter indexes following the name index
+    #[serde(default)]
+    pub indexes: Vec<BrochureIndex>,
+    // Day grids to go before the listings, keyed by date and then screen as for Summary
+    #[serde(default)]
+    pub grid: Option<TypstMap<TypstMap<Vec<Showing>>>>,
+    // Differences from the last published version, for the "what changed" page
+    #[serde(default)]
+    pub changes: Option<BrochureChanges>,
+    // What each symbol on a showing line means, for those that are used
+    #[serde(default)]
+    pub badge_key: Vec<ScreeningBadge>,
+}
+
+impl Brochure {
+    /// The brochure for `entries`, with its indexes and badge key built from them and
+    /// their details joined up.
+    pub fn new(mut entries: Vec<BrochureEntry>, version: &str) -> Self {
+        for entry in &mut entries {
+            entry.made = entry.made();
+            entry.spoken = entry.spoken();
+        }
+        let mut badge_key = entries
+            .iter()
+            .flat_map(|e| e.showings.iter().flat_map(|s| s.badges.iter()))
+            .cloned()
+            .collect::<Vec<_>>();
+        badge_key.sort_by(|a, b| a.name.cmp(&b.name));
+        badge_key.dedup();
+        Brochure {
+            badge_key,
+            indexes: brochure_indexes(&entries),
+            entries,
+            version: version.to_string(),
+            grid: None,
+            changes: None,
+        }
+    }
+}
+
+#[derive(Clone, Default, IntoValue, Serialize, Deserialize)]
+pub struct BrochureEntry {
+    pub name: String,
+    pub id: String,
+    pub sortname: String,
+    pub showings: Vec<BrochureShowing>,
+    pub duration: u64,
+    // Typst markup for the pdf
+    pub synopsis: String,
+    // The same as html, for the html and epub brochures
+    #[serde(default)]
+    pub synopsis_html: String,
+    pub starring: String,
+    pub genres: String,
+    #[serde(rename = "directedBy")]
+    pub directed_by: String,
+    pub rating: String,
+    #[serde(rename = "ratingReason")]
+    pub rating_reason: String,
+    pub strand: String,
+    // Strand colour for the placeholder card when there's no poster
+    #[serde(default)]
+    pub colour: String,
+    pub poster: String,
+    #[serde(skip)]
+    pub poster_bytes: Option<Vec<u8>>,
+    // Not in brochures published before these were fetched
+    #[serde(default)]
+    pub year: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub subtitles: String,
+    #[serde(default)]
+    pub certificate: String,
+    // Youtube url
+    #[serde(default)]
+    pub trailer: String,
+    #[serde(default)]
+    pub secondary: Vec<StrandMarker>,
+    // made() and spoken(), filled in by Brochure::new for the template
+    #[serde(skip)]
+    pub made: String,
+    #[serde(skip)]
+    pub spoken: String,
+}
+
+/// The parts that are known, separated by commas.
+pub fn join_known(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl BrochureEntry {
+    /// Year and country, as far as they're known.
+    pub fn made(&self) -> String {
+        join_known(&[&self.year, &self.country])
+    }
+    /// Language and subtitles, as far as they're known.
+    pub fn spoken(&self) -> String {
+        join_known(&[&self.language, &self.subtitles])
+    }
+    /// Every strand the film is in with its colour, the main one first.
+    pub fn strands(&self) -> Vec<(&str, &str)> {
+        let main = Some((self.strand.trim(), self.colour.as_str())).filter(|s| !s.0.is_empty());
+        main.into_iter()
+            .chain(
+                self.secondary
+                    .iter()
+                    .map(|s| (s.name.as_str(), s.colour.as_str())),
+            )
+            .collect()
+    }
+}
+
+#[derive(Clone, Default, IntoValue, Serialize, Deserialize)]
+pub struct BrochureShowing {
+    pub screen: String,
+    pub time: String,
+    pub date: String,
+    pub datetime: String,
+    // New or moved since the last published version, see brochure_changes
+    #[serde(default, skip_serializing)]
+    pub changed: bool,
+    #[serde(default)]
+    pub badges: Vec<ScreeningBadge>,
+}
+
+#[derive(IntoValue, IntoDict)]
+pub struct Summary {
+    pub version: String,
+    pub summary: TypstMap<TypstMap<Vec<Showing>>>,
+    pub colours: TypstMap<String>,
+    pub names: TypstMap<String>,
+    // Filtered copies of the grids to follow the full one, see filter_summary_pdf
+    pub filters: Vec<SummaryFilter>,
+    // Start with the grids of every screening, before any filtered ones
+    pub full: bool,
+}
+
+/// The screenings attended by any of `people`, given as initials, headed by `title`.
+#[derive(Clone, IntoValue, Serialize, Deserialize)]
+pub struct SummaryFilter {
+    pub title: String,
+    pub people: Vec<String>,
+}
+
+/// A strand a film is in besides its main one, for a marker in its colour.
+#[derive(Clone, Debug, Default, PartialEq, IntoTypst, Serialize, Deserialize)]
+pub struct StrandMarker {
+    pub name: String,
+    pub colour: String,
+}
+
+/// An attribute of one screening, such as captioned, with the symbol shown for it.
+#[derive(Clone, Debug, Default, PartialEq, IntoTypst, Serialize, Deserialize)]
+pub struct ScreeningBadge {
+    pub name: String,
+    pub symbol: String,
+}
+
+#[derive(Clone, Debug, IntoValue, Serialize, Deserialize)]
+pub struct Showing {
+    pub start: String,
+    pub title: String,
+    pub strand: String,
+    pub duration: u64,
+    pub color: String,
+    pub id: Option<String>,
+    pub day: String,
+    pub attendees: Vec<String>,
+    #[serde(default)]
+    pub secondary: Vec<StrandMarker>,
+    #[serde(default)]
+    pub badges: Vec<ScreeningBadge>,
+    // Time allowed after the film, such as for a Q&A, drawn after the `duration`
+    #[serde(default)]
+    pub extra_minutes: u64,
+}
+
+// The templates are baked in, but a directory of replacements can be given at runtime
+// so a layout tweak doesn't need a rebuild.
+
+pub fn brochure_pdf(brochure: Brochure, templates: Option<&Path>) -> Result<Vec<u8>, RenderError> {
+    templates::compile_pdf(templates, "brochure.typ", brochure)
+}
+
+/// The day grids with every screening, screens as rows.
+pub fn summary_pdf(summary: Summary, templates: Option<&Path>) -> Result<Vec<u8>, RenderError> {
+    templates::compile_pdf(templates, "summary.typ", summary)
+}
+
+/// The day grids with a row for each person in names, rather than each screen.
+pub fn person_summary_pdf(
+    summary: Summary,
+    templates: Option<&Path>,
+) -> Result<Vec<u8>, RenderError> {
+    templates::compile_pdf(templates, "person-summary.typ", summary)
+}
+
+/// The full day grids, if `full` is set, followed by a filtered set for each of the
+/// `filters`.
+pub fn filter_summary_pdf(
+    summary: Summary,
+    templates: Option<&Path>,
+) -> Result<Vec<u8>, RenderError> {
+    templates::compile_pdf(templates, "filter-summary.typ", summary)
+}
+
+/// True if typst will be able to decode the data as an image.
+pub fn valid_poster(data: &[u8]) -> bool {
+    ExchangeFormat::detect(data)
+        .map(|format| RasterImage::plain(Bytes::new(data.to_vec()), format).is_ok())
+        .unwrap_or(false)
+}
+
+/// Read each entry's poster, resolving relative paths against `base`.  Entries whose
+/// poster is missing or can't be decoded are left without one, and the template draws
+/// a placeholder card instead.  Returns the names of those films.
+pub fn load_pictures(entries: &mut [BrochureEntry], base: &Path) -> Vec<String> {
+    let mut missing = vec![];
+    for entry in entries {
+        entry.poster_bytes = std::fs::read(base.join(&entry.poster))
+            .ok()
+            .filter(|data| valid_poster(data));
+        if entry.poster_bytes.is_none() {
+            missing.push(entry.name.clone());
+        }
+    }
+    missing
+}
+
+pub fn save_pdf(data: &[u8], filename: &Path) -> Result<(), RenderError> {
+    std::fs::write(filename, data)
+        .map_err(|_| RenderError::WriteError(filename.to_string_lossy().to_string()))?;
+    println!("Generated {} ({} bytes)", filename.display(), data.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn entry(name: &str, poster: &str) -> BrochureEntry {
+        BrochureEntry {
+            name: name.to_string(),
+            id: "1".to_string(),
+            sortname: name.to_string(),
+            duration: 90,
+            rating: "15".to_string(),
+            colour: "6596d0".to_string(),
+            poster: poster.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn summary(screens: &[&str]) -> Summary {
+        let mut day = std::collections::HashMap::new();
+        for screen in screens {
+            day.insert(
+                screen.to_string(),
+                vec![Showing {
+                    start: "13:00".to_string(),
+                    title: format!("Film in {}", screen),
+                    strand: "".to_string(),
+                    duration: 90,
+                    color: "6596d0".to_string(),
+                    id: None,
+                    day: "Wednesday".to_string(),
+                    attendees: vec![],
+                    secondary: vec![StrandMarker {
+                        name: "Gala".to_string(),
+                        colour: "f2c170".to_string(),
+                    }],
+                    badges: vec![ScreeningBadge {
+                        name: "Audio described".to_string(),
+                        symbol: "AD".to_string(),
+                    }],
+                    extra_minutes: 30,
+                }],
+            );
+        }
+        let mut data = TypstMap::sorted();
+        data.insert("2026-02-25".to_string(), TypstMap::from(day));
+        Summary {
+            version: "1".to_string(),
+            summary: data,
+            colours: TypstMap::default(),
+            names: TypstMap::default(),
+            filters: vec![],
+            full: true,
+        }
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let first = summary_pdf(summary(&["GFT 1", "GFT 2", "Odeon 10"]), None).unwrap();
+        let second = summary_pdf(summary(&["Odeon 10", "GFT 2", "GFT 1"]), None).unwrap();
+        assert!(first == second);
+    }
+
+    #[test]
+    fn test_person_summary() {
+        let mut inputs = summary(&["GFT 1", "Odeon 10"]);
+        inputs.names = TypstMap::from(std::collections::HashMap::from([(
+            "N".to_string(),
+            "Neil".to_string(),
+        )]));
+        for showings in inputs.summary.values_mut().flat_map(|day| day.values_mut()) {
+            showings[0].attendees = vec!["N".to_string()];
+        }
+        let pdf = person_summary_pdf(inputs, None).unwrap();
+        assert_eq!(b"%PDF", &pdf[0..4]);
+    }
+
+    #[test]
+    fn test_brochure_indexes() {
+        let mut entries = vec![entry("Fargo", ""), entry("Barton Fink", "")];
+        for (i, e) in entries.iter_mut().enumerate() {
+            e.id = format!("{}", i + 1);
+            e.strand = "Coen Brothers".to_string();
+            e.directed_by = "Joel Coen, Ethan Coen".to_string();
+            e.genres = "Comedy".to_string();
+        }
+        entries[0].year = "1996".to_string();
+        entries[0].language = "English".to_string();
+        entries[0].trailer = "https://www.youtube.com/watch?v=h2tY82z3xXU".to_string();
+        entries[1].secondary = vec![StrandMarker {
+            name: "Gala".to_string(),
+            colour: "f2c170".to_string(),
+        }];
+        let brochure = Brochure::new(entries, "1");
+        assert_eq!(3, brochure.indexes.len());
+        assert_eq!(2, brochure.indexes[0].groups.len());
+        let pdf = brochure_pdf(brochure, None).unwrap();
+        assert_eq!(b"%PDF", &pdf[0..4]);
+    }
+
+    #[test]
+    fn test_details() {
+        let mut film = entry("Fargo", "");
+        film.year = "1996".to_string();
+        film.country = " USA ".to_string();
+        film.subtitles = "English subtitles".to_string();
+        assert_eq!("1996, USA", film.made());
+        assert_eq!("English subtitles", film.spoken());
+        let brochure = Brochure::new(vec![film], "1");
+        assert_eq!("1996, USA", brochure.entries[0].made);
+        assert_eq!("English subtitles", brochure.entries[0].spoken);
+    }
+
+    #[test]
+    fn test_brochure_grid() {
+        let mut film = entry("Film in GFT 1", "");
+        film.showings.push(BrochureShowing {
+            screen: "GFT 1".to_string(),
+            time: "13:00".to_string(),
+            date: "Wednesday 25th February".to_string(),
+            datetime: "2026-02-25T13:00:00Z".to_string(),
+            changed: true,
+            badges: vec![ScreeningBadge {
+                name: "Captioned".to_string(),
+                symbol: "CC".to_string(),
+            }],
+        });
+        let mut grid = summary(&["GFT 1", "GFT 2"]).summary;
+        grid["2026-02-25"]["GFT 1"][0].id = Some("1".to_string());
+        let mut brochure = Brochure::new(vec![film], "1");
+        assert_eq!("CC", brochure.badge_key[0].symbol);
+        brochure.grid = Some(grid);
+        let change = |id: &str, was: &str, now: &str| ShowingChange {
+            name: "Film in GFT 1".to_string(),
+            id: id.to_string(),
+            was: was.to_string(),
+            now: now.to_string(),
+        };
+        brochure.changes = Some(BrochureChanges {
+            since: "7".to_string(),
+            new_films: vec![],
+            added: vec![change("1", "", "Wed 13:00, GFT 1")],
+            cancelled: vec![change("", "Thu 18:00, GFT 2", "")],
+            moved: vec![change("1", "Fri 11:00, GFT 3", "Fri 11:00, GFT 1")],
+        });
+        let pdf = brochure_pdf(brochure, None).unwrap();
+        assert_eq!(b"%PDF", &pdf[0..4]);
+    }
+
+    #[test]
+    fn test_load_pictures() {
+        let dir = std::env::temp_dir().join(format!("summary-posters-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("corrupt.jpg"), b"\xff\xd8\xff\xe0 not really").unwrap();
+        let templates = Path::new(env!("CARGO_MANIFEST_DIR")).join("templates");
+        std::fs::copy(templates.join("banner.jpg"), dir.join("good.jpg")).unwrap();
+
+        let mut entries = vec![
+            entry("Good", "good.jpg"),
+            entry("Corrupt", "corrupt.jpg"),
+            entry("Missing", "missing.jpg"),
+        ];
+        let missing = load_pictures(&mut entries, &dir);
+        assert_eq!(vec!["Corrupt", "Missing"], missing);
+        assert!(entries[0].poster_bytes.is_some());
+        assert!(entries[1].poster_bytes.is_none());
+
+        let pdf = brochure_pdf(Brochure::new(entries, "1"), None);
+        assert!(pdf.is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,63 +1,6 @@
-mod typst_map;
-use serde::{Deserialize, Serialize};
-use typst_bake::{IntoDict, IntoValue};
-use typst_map::TypstMap;
+use std::path::Path;
+use summary::{brochure_pdf, load_pictures, save_pdf, Brochure, BrochureEntry};
 
-#[derive(IntoValue, IntoDict, Serialize, Deserialize)]
-struct Brochure {
-    pub entries: Vec<BrochureEntry>,
-    pub banner: Vec<u8>,
-    pub version: String,
-}
-
-#[derive(IntoValue, Serialize, Deserialize)]
-struct BrochureEntry {
-    pub name: String,
-    pub id: String,
-    pub sortname: String,
-    pub showings: Vec<BrochureShowing>,
-    pub duration: u64,
-    pub synopsis: String,
-    pub starring: String,
-    pub genres: String,
-    #[serde(rename = "directedBy")]
-    pub directed_by: String,
-    pub rating: String,
-    #[serde(rename = "ratingReason")]
-    pub rating_reason: String,
-    pub strand: String,
-    pub poster: String,
-    #[serde(skip)]
-    pub poster_bytes: Option<Vec<u8>>,
-}
-
-#[derive(IntoValue, Serialize, Deserialize)]
-struct BrochureShowing {
-    pub screen: String,
-    pub time: String,
-    pub date: String,
-    pub datetime: String,
-}
-
-#[derive(IntoValue, IntoDict)]
-struct Summary {
-    pub version: String,
-    pub summary: TypstMap<TypstMap<Vec<Showing>>>,
-    pub colours: TypstMap<String>,
-    pub names: TypstMap<String>,
-}
-
-#[derive(Clone, IntoValue, Serialize, Deserialize)]
-struct Showing {
-    start: String,
-    title: String,
-    strand: String,
-    duration: u64,
-    color: String,
-    id: Option<String>,
-    day: String,
-    attendees: Vec<String>,
-}
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     /*
         let colours: TypstMap<String> = vec![
@@ -90,38 +33,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .with_inputs(summary)
             .to_pdf()?;
     */
-    let json = std::fs::read("brochure.json").unwrap();
-    let mut entries: Vec<BrochureEntry> = serde_json::from_slice(&json[..]).unwrap();
-    let banner = std::fs::read("banner.jpg").unwrap();
-    load_pictures(&mut entries);
-    let brochure = Brochure {
-        entries,
-        banner,
-        version: "1".to_owned(),
-    };
-    let pdf = typst_bake::document!("brochure.typ")
-        .with_inputs(brochure)
-        .to_pdf()?;
-    save_pdf(&pdf, "brochure.pdf")
-}
-
-fn load_pictures(entries: &mut Vec<BrochureEntry>) {
-    for entry in entries {
-        let data = std::fs::read(&entry.poster).unwrap();
-        entry.poster_bytes = Some(data);
+    let args = std::env::args().collect::<Vec<_>>();
+    let input = args.get(1).map(|e| e.as_str()).unwrap_or("brochure.json");
+    let output = args.get(2).map(|e| e.as_str()).unwrap_or("brochure.pdf");
+    let templates = args.get(3).map(Path::new);
+    let json = std::fs::read(input)?;
+    let mut entries: Vec<BrochureEntry> = serde_json::from_slice(&json[..])?;
+    let base = Path::new(input).parent().unwrap_or(Path::new("."));
+    let missing = load_pictures(&mut entries, base);
+    let brochure = Brochure::new(entries, "1");
+    save_pdf(&brochure_pdf(brochure, templates)?, Path::new(output))?;
+    if !missing.is_empty() {
+        println!("No artwork for {} films:", missing.len());
+        for name in missing {
+            println!("  {}", name);
+        }
     }
-}
-
-fn save_pdf(data: &[u8], filename: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let out_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
-    std::fs::write(out_dir.join(filename), data)?;
-    println!("Generated {} ({} bytes)", filename, data.len());
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde::{Deserialize, Serialize};
+    use summary::{Showing, TypstMap};
+    use typst_bake::{IntoDict, IntoValue};
     fn save_svg(data: &[String], filename: &str) -> Result<(), Box<dyn std::error::Error>> {
         let out_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
         std::fs::write(out_dir.join(filename), data.first().unwrap())?;
@@ -165,6 +101,6 @@ mod test {
             .with_inputs(data)
             .to_pdf()
             .unwrap();
-        save_pdf(&pdf, "output.pdf").unwrap();
+        save_pdf(&pdf, Path::new("output.pdf")).unwrap();
     }
 }
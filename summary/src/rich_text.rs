@@ -0,0 +1,869 @@
+use crate::html::escape;
+
+/// Formatted text within a paragraph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Emph(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Link { href: String, children: Vec<Inline> },
+    Break,
+}
+
+/// A paragraph, list or horizontal rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    Paragraph(Vec<Inline>),
+    List {
+        ordered: bool,
+        items: Vec<Vec<Inline>>,
+    },
+    Rule,
+}
+
+/// The formatting GFT's html synopses use, independent of how it's going to be shown.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RichText {
+    pub blocks: Vec<Block>,
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "pound" => '£',
+        "euro" => '€',
+        "eacute" => 'é',
+        "egrave" => 'è',
+        "aacute" => 'á',
+        "agrave" => 'à',
+        "iacute" => 'í',
+        "oacute" => 'ó',
+        "uacute" => 'ú',
+        "ntilde" => 'ñ',
+        "ccedil" => 'ç',
+        "ouml" => 'ö',
+        "uuml" => 'ü',
+        "auml" => 'ä',
+        "szlig" => 'ß',
+        _ => return None,
+    })
+}
+
+/// Replace character references, leaving anything unrecognised as it was.
+fn decode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity(&rest[1..end + 1])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text(String),
+    Open { name: String, href: Option<String> },
+    Close(String),
+}
+
+// Attributes are only needed for links, so only href is kept
+fn href(attrs: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(i) = rest.to_ascii_lowercase().find("href") {
+        rest = rest[i + 4..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let value = match quote {
+            Some(q) => value[1..].split(q).next().unwrap_or(""),
+            None => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or(""),
+        };
+        return Some(decode(value));
+    }
+    None
+}
+
+/// Split html into text and tags, skipping comments, declarations and the contents of
+/// style and script elements.  A `<` that doesn't start a tag is text.
+fn tokens(html: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(i) = rest.find('<') {
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let skip_to = |rest: &str, end: &str| rest.find(end).map(|j| j + end.len());
+        if rest.starts_with("<!--") {
+            rest = &rest[skip_to(rest, "-->").unwrap_or(rest.len())..];
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = &rest[skip_to(rest, ">").unwrap_or(rest.len())..];
+            continue;
+        }
+        let closing = rest[1..].starts_with('/');
+        let start = if closing { 2 } else { 1 };
+        let name_len = rest[start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len() - start);
+        let Some(end) = rest.find('>').filter(|_| name_len > 0) else {
+            text.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+        let name = rest[start..start + name_len].to_ascii_lowercase();
+        let attrs = &rest[start + name_len..end];
+        rest = &rest[end + 1..];
+        if !text.is_empty() {
+            tokens.push(Token::Text(decode(&std::mem::take(&mut text))));
+        }
+        if closing {
+            tokens.push(Token::Close(name));
+        } else if name == "style" || name == "script" {
+            let close = format!("</{}", name);
+            let end = rest.to_ascii_lowercase().find(&close);
+            rest = match end {
+                Some(j) => &rest[skip_to(&rest[j..], ">").map_or(rest.len(), |k| j + k)..],
+                None => "",
+            };
+        } else {
+            let href = if name == "a" { href(attrs) } else { None };
+            let self_closing = attrs.trim_end().ends_with('/');
+            tokens.push(Token::Open {
+                name: name.clone(),
+                href,
+            });
+            if self_closing && !matches!(name.as_str(), "br" | "hr") {
+                tokens.push(Token::Close(name));
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(decode(&text)));
+    }
+    tokens
+}
+
+enum Kind {
+    Root,
+    Emph,
+    Strong,
+    Link(String),
+    // Formatting that isn't kept, such as font or span, whose text still is
+    Plain,
+}
+
+struct Frame {
+    tag: String,
+    kind: Kind,
+    children: Vec<Inline>,
+}
+
+impl Frame {
+    fn new(tag: &str, kind: Kind) -> Self {
+        Frame {
+            tag: tag.to_string(),
+            kind,
+            children: vec![],
+        }
+    }
+}
+
+fn push_text(children: &mut Vec<Inline>, text: &str) {
+    if let Some(Inline::Text(last)) = children.last_mut() {
+        last.push_str(text);
+    } else {
+        children.push(Inline::Text(text.to_string()));
+    }
+}
+
+// Collapse whitespace as a browser would, dropping it at the edges and around breaks
+fn normalise(children: Vec<Inline>) -> Vec<Inline> {
+    fn collapse(children: Vec<Inline>, out: &mut Vec<Inline>, space: &mut bool) {
+        for child in children {
+            match child {
+                Inline::Text(text) => {
+                    let mut collapsed = String::new();
+                    for c in text.chars() {
+                        if c.is_whitespace() && c != '\u{a0}' {
+                            if !*space {
+                                collapsed.push(' ');
+                            }
+                            *space = true;
+                        } else {
+                            collapsed.push(c);
+                            *space = false;
+                        }
+                    }
+                    if !collapsed.is_empty() {
+                        push_text(out, &collapsed);
+                    }
+                }
+                Inline::Break => {
+                    if let Some(Inline::Text(last)) = out.last_mut() {
+                        last.truncate(last.trim_end_matches(' ').len());
+                    }
+                    out.push(Inline::Break);
+                    *space = true;
+                }
+                Inline::Emph(c) => nested(c, out, space, Inline::Emph),
+                Inline::Strong(c) => nested(c, out, space, Inline::Strong),
+                Inline::Link { href, children } => {
+                    nested(children, out, space, |children| Inline::Link {
+                        href: href.clone(),
+                        children,
+                    })
+                }
+            }
+        }
+    }
+    fn nested(
+        children: Vec<Inline>,
+        out: &mut Vec<Inline>,
+        space: &mut bool,
+        wrap: impl FnOnce(Vec<Inline>) -> Inline,
+    ) {
+        let mut inner = vec![];
+        collapse(children, &mut inner, space);
+        if !inner.is_empty() {
+            out.push(wrap(inner));
+        }
+    }
+    fn trim(children: &mut Vec<Inline>, start: bool) {
+        let child = if start {
+            children.first_mut()
+        } else {
+            children.last_mut()
+        };
+        let empty = match child {
+            Some(Inline::Text(text)) => {
+                *text = if start {
+                    text.trim_start_matches(' ').to_string()
+                } else {
+                    text.trim_end_matches(' ').to_string()
+                };
+                text.is_empty()
+            }
+            Some(Inline::Emph(c) | Inline::Strong(c) | Inline::Link { children: c, .. }) => {
+                trim(c, start);
+                c.is_empty()
+            }
+            Some(Inline::Break) | None => false,
+        };
+        if empty {
+            if start {
+                children.remove(0);
+            } else {
+                children.pop();
+            }
+            trim(children, start);
+        }
+    }
+    let mut out = vec![];
+    collapse(children, &mut out, &mut true);
+    // Leading and trailing breaks say nothing
+    while out.first() == Some(&Inline::Break) {
+        out.remove(0);
+    }
+    while out.last() == Some(&Inline::Break) {
+        out.pop();
+    }
+    trim(&mut out, true);
+    trim(&mut out, false);
+    out
+}
+
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    frames: Vec<Frame>,
+    list: Option<(bool, Vec<Vec<Inline>>)>,
+    depth: usize,
+    in_item: bool,
+}
+
+impl Builder {
+    fn top(&mut self) -> &mut Vec<Inline> {
+        if self.frames.is_empty() {
+            self.frames.push(Frame::new("", Kind::Root));
+        }
+        &mut self.frames.last_mut().unwrap().children
+    }
+
+    fn open(&mut self, tag: &str, kind: Kind) {
+        self.top();
+        self.frames.push(Frame::new(tag, kind));
+    }
+
+    fn pop(&mut self) {
+        let Some(frame) = self.frames.pop() else {
+            return;
+        };
+        let inline = match frame.kind {
+            Kind::Root => {
+                self.frames.push(frame);
+                return;
+            }
+            Kind::Emph => Inline::Emph(frame.children),
+            Kind::Strong => Inline::Strong(frame.children),
+            Kind::Link(href) => Inline::Link {
+                href,
+                children: frame.children,
+            },
+            Kind::Plain => {
+                for child in frame.children {
+                    match child {
+                        Inline::Text(text) => push_text(self.top(), &text),
+                        other => self.top().push(other),
+                    }
+                }
+                return;
+            }
+        };
+        self.top().push(inline);
+    }
+
+    fn close(&mut self, tag: &str) {
+        if let Some(i) = self.frames.iter().rposition(|f| f.tag == tag) {
+            while self.frames.len() > i {
+                self.pop();
+            }
+        }
+    }
+
+    /// End the paragraph or list item being built.
+    fn flush(&mut self) {
+        while self.frames.len() > 1 {
+            self.pop();
+        }
+        let children = normalise(self.frames.pop().map(|f| f.children).unwrap_or_default());
+        if children.is_empty() {
+            return;
+        }
+        match &mut self.list {
+            Some((_, items)) => {
+                if !self.in_item || items.is_empty() {
+                    items.push(vec![]);
+                    self.in_item = true;
+                }
+                let item = items.last_mut().unwrap();
+                if !item.is_empty() {
+                    item.push(Inline::Text(" ".to_string()));
+                }
+                item.extend(children);
+            }
+            None => self.blocks.push(Block::Paragraph(children)),
+        }
+    }
+
+    fn end_list(&mut self) {
+        self.flush();
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0
+            && let Some((ordered, items)) = self.list.take()
+        {
+            let items = items
+                .into_iter()
+                .filter(|i| !i.is_empty())
+                .collect::<Vec<_>>();
+            if !items.is_empty() {
+                self.blocks.push(Block::List { ordered, items });
+            }
+            self.in_item = false;
+        }
+    }
+
+    fn token(&mut self, token: Token) {
+        match token {
+            Token::Text(text) => push_text(self.top(), &text),
+            Token::Open { name, href } => match name.as_str() {
+                "i" | "em" | "cite" => self.open(&name, Kind::Emph),
+                "b" | "strong" => self.open(&name, Kind::Strong),
+                "a" => self.open(
+                    &name,
+                    href.filter(|h| !h.is_empty())
+                        .map_or(Kind::Plain, Kind::Link),
+                ),
+                "br" => self.top().push(Inline::Break),
+                "hr" => {
+                    self.flush();
+                    if self.list.is_none() {
+                        self.blocks.push(Block::Rule);
+                    }
+                }
+                "ul" | "ol" => {
+                    self.flush();
+                    if self.list.is_none() {
+                        self.list = Some((name == "ol", vec![]));
+                    }
+                    self.depth += 1;
+                }
+                "li" => {
+                    self.flush();
+                    if self.list.is_none() {
+                        self.list = Some((false, vec![]));
+                        self.depth = 1;
+                    }
+                    if let Some((_, items)) = &mut self.list {
+                        items.push(vec![]);
+                    }
+                    self.in_item = true;
+                }
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                    self.flush();
+                    self.open(&name, Kind::Strong);
+                }
+                "p" | "div" | "blockquote" | "section" | "article" | "table" | "tr" => self.flush(),
+                _ => self.open(&name, Kind::Plain),
+            },
+            Token::Close(name) => match name.as_str() {
+                "ul" | "ol" => self.end_list(),
+                "li" => {
+                    self.flush();
+                    self.in_item = false;
+                }
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                    self.close(&name);
+                    self.flush();
+                }
+                "p" | "div" | "blockquote" | "section" | "article" | "table" | "tr" => self.flush(),
+                _ => self.close(&name),
+            },
+        }
+    }
+}
+
+// Quotes are left alone so typst still makes them smart
+fn typst_escape(text: &str, out: &mut String) {
+    let mut digit = false;
+    for c in text.chars() {
+        match c {
+            '\\' | '#' | '*' | '_' | '$' | '[' | ']' | '<' | '>' | '@' | '`' | '~' | '=' | '-'
+            | '+' | '/' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // 1. would start a numbered list
+            '.' if digit => out.push_str("\\."),
+            '\u{a0}' => out.push('~'),
+            c => out.push(c),
+        }
+        digit = c.is_ascii_digit();
+    }
+}
+
+fn typst_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn markdown_escape(text: &str, out: &mut String) {
+    let mut digit = false;
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '`' | '|' | '-' | '+' | '!'
+        ) || (c == '.' && digit)
+        {
+            out.push('\\');
+        }
+        out.push(c);
+        digit = c.is_ascii_digit();
+    }
+}
+
+impl RichText {
+    /// Parse html leniently: unknown tags keep their text, misnested tags are closed
+    /// where their parent closes, and anything left open is closed at the end.
+    pub fn parse(html: &str) -> Self {
+        let mut builder = Builder::default();
+        for token in tokens(html) {
+            builder.token(token);
+        }
+        while builder.list.is_some() {
+            builder.end_list();
+        }
+        builder.flush();
+        RichText {
+            blocks: builder.blocks,
+        }
+    }
+
+    /// Plain text, blank lines separating paragraphs and other newlines kept as breaks.
+    pub fn from_plain(text: &str) -> Self {
+        let blocks = text
+            .replace("\r\n", "\n")
+            .split("\n\n")
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let mut children = vec![];
+                for (i, line) in p.lines().enumerate() {
+                    if i > 0 {
+                        children.push(Inline::Break);
+                    }
+                    children.push(Inline::Text(line.to_string()));
+                }
+                Block::Paragraph(children)
+            })
+            .collect();
+        RichText { blocks }
+    }
+
+    fn render(
+        &self,
+        inline: &dyn Fn(&[Inline], &mut String),
+        list_marker: &dyn Fn(bool, usize) -> String,
+        rule: &str,
+    ) -> String {
+        self.blocks
+            .iter()
+            .map(|block| {
+                let mut out = String::new();
+                match block {
+                    Block::Paragraph(children) => inline(children, &mut out),
+                    Block::List { ordered, items } => {
+                        for (i, item) in items.iter().enumerate() {
+                            if i > 0 {
+                                out.push('\n');
+                            }
+                            out.push_str(&list_marker(*ordered, i));
+                            inline(item, &mut out);
+                        }
+                    }
+                    Block::Rule => out.push_str(rule),
+                }
+                out
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Typst markup, with every character typst gives a meaning to escaped.
+    pub fn to_typst(&self) -> String {
+        fn inline(children: &[Inline], out: &mut String) {
+            for child in children {
+                match child {
+                    Inline::Text(text) => typst_escape(text, out),
+                    Inline::Emph(c) => {
+                        out.push_str("#emph[");
+                        inline(c, out);
+                        out.push(']');
+                    }
+                    Inline::Strong(c) => {
+                        out.push_str("#strong[");
+                        inline(c, out);
+                        out.push(']');
+                    }
+                    Inline::Link { href, children } => {
+                        out.push_str(&format!("#link({})[", typst_string(href)));
+                        inline(children, out);
+                        out.push(']');
+                    }
+                    Inline::Break => out.push_str(" \\\n"),
+                }
+            }
+        }
+        self.render(
+            &inline,
+            &|ordered, _| if ordered { "+ " } else { "- " }.to_string(),
+            "#line(length: 100%)",
+        )
+    }
+
+    /// Html that is also valid xhtml, for the html and epub brochures.
+    pub fn to_html(&self) -> String {
+        fn inline(children: &[Inline], out: &mut String) {
+            for child in children {
+                match child {
+                    Inline::Text(text) => out.push_str(&escape(text)),
+                    Inline::Emph(c) => {
+                        out.push_str("<em>");
+                        inline(c, out);
+                        out.push_str("</em>");
+                    }
+                    Inline::Strong(c) => {
+                        out.push_str("<strong>");
+                        inline(c, out);
+                        out.push_str("</strong>");
+                    }
+                    Inline::Link { href, children } => {
+                        out.push_str(&format!("<a href=\"{}\">", escape(href)));
+                        inline(children, out);
+                        out.push_str("</a>");
+                    }
+                    Inline::Break => out.push_str("<br/>"),
+                }
+            }
+        }
+        self.blocks
+            .iter()
+            .map(|block| {
+                let mut out = String::new();
+                match block {
+                    Block::Paragraph(children) => {
+                        out.push_str("<p>");
+                        inline(children, &mut out);
+                        out.push_str("</p>");
+                    }
+                    Block::List { ordered, items } => {
+                        let tag = if *ordered { "ol" } else { "ul" };
+                        out.push_str(&format!("<{}>", tag));
+                        for item in items {
+                            out.push_str("<li>");
+                            inline(item, &mut out);
+                            out.push_str("</li>");
+                        }
+                        out.push_str(&format!("</{}>", tag));
+                    }
+                    Block::Rule => out.push_str("<hr/>"),
+                }
+                out
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// CommonMark, escaping anything that could be read as formatting.
+    pub fn to_markdown(&self) -> String {
+        fn inline(children: &[Inline], out: &mut String) {
+            for child in children {
+                match child {
+                    Inline::Text(text) => markdown_escape(text, out),
+                    Inline::Emph(c) => {
+                        out.push('*');
+                        inline(c, out);
+                        out.push('*');
+                    }
+                    Inline::Strong(c) => {
+                        out.push_str("**");
+                        inline(c, out);
+                        out.push_str("**");
+                    }
+                    Inline::Link { href, children } => {
+                        out.push('[');
+                        inline(children, out);
+                        out.push_str(&format!("](<{}>)", href.replace('>', "%3E")));
+                    }
+                    Inline::Break => out.push_str("\\\n"),
+                }
+            }
+        }
+        self.render(
+            &inline,
+            &|ordered, i| {
+                if ordered {
+                    format!("{}. ", i + 1)
+                } else {
+                    "- ".to_string()
+                }
+            },
+            "---",
+        )
+    }
+
+    /// Plain text, with a link's address after its text unless they're the same.
+    pub fn to_plain(&self) -> String {
+        fn inline(children: &[Inline], out: &mut String) {
+            for child in children {
+                match child {
+                    Inline::Text(text) => out.push_str(text),
+                    Inline::Emph(c) | Inline::Strong(c) => inline(c, out),
+                    Inline::Link { href, children } => {
+                        let mut text = String::new();
+                        inline(children, &mut text);
+                        out.push_str(&text);
+                        if text.trim() != href.trim_start_matches("mailto:") {
+                            out.push_str(&format!(" ({})", href));
+                        }
+                    }
+                    Inline::Break => out.push('\n'),
+                }
+            }
+        }
+        self.render(
+            &inline,
+            &|ordered, i| {
+                if ordered {
+                    format!("{}. ", i + 1)
+                } else {
+                    "- ".to_string()
+                }
+            },
+            "----",
+        )
+        .replace('\u{a0}', " ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Inline {
+        Inline::Text(s.to_string())
+    }
+
+    // Synopses in the shapes the GFT site sends: typed straight in, pasted from Word
+    // with fonts and spans, with links to partners and with content warnings set apart
+    // with a line of underscores or a rule.
+    const SYNOPSES: &[(&str, &str, &str, &str, &str)] = &[
+        (
+            "<p>Chlo&eacute; Zhao&rsquo;s <i>Hamnet</i> stars Jessie Buckley &amp; Paul Mescal.</p>",
+            "Chloé Zhao’s #emph[Hamnet] stars Jessie Buckley & Paul Mescal.",
+            "Chloé Zhao’s *Hamnet* stars Jessie Buckley & Paul Mescal.",
+            "Chloé Zhao’s Hamnet stars Jessie Buckley & Paul Mescal.",
+            "<p>Chloé Zhao’s <em>Hamnet</em> stars Jessie Buckley &amp; Paul Mescal.</p>",
+        ),
+        (
+            "<p><strong>UK PREMIERE</strong><br />\n<em>Tickets £5 for under-25s*</em></p>",
+            "#strong[UK PREMIERE] \\\n#emph[Tickets £5 for under\\-25s\\*]",
+            "**UK PREMIERE**\\\n*Tickets £5 for under\\-25s\\**",
+            "UK PREMIERE\nTickets £5 for under-25s*",
+            "<p><strong>UK PREMIERE</strong><br/><em>Tickets £5 for under-25s*</em></p>",
+        ),
+        (
+            "<p><font face=\"Arial\"><span style=\"color: #000\">A <b>bold <i>and</i></b> <i>nested</i> story.</span></font></p>",
+            "A #strong[bold #emph[and]] #emph[nested] story.",
+            "A **bold *and*** *nested* story.",
+            "A bold and nested story.",
+            "<p>A <strong>bold <em>and</em></strong> <em>nested</em> story.</p>",
+        ),
+        (
+            "<p>Presented with <a href=\"https://www.example.org/partner?a=1&amp;b=2\" target=\"_blank\">our partners</a>.</p>",
+            "Presented with #link(\"https://www.example.org/partner?a=1&b=2\")[our partners].",
+            "Presented with [our partners](<https://www.example.org/partner?a=1&b=2>).",
+            "Presented with our partners (https://www.example.org/partner?a=1&b=2).",
+            "<p>Presented with <a href=\"https://www.example.org/partner?a=1&amp;b=2\">our partners</a>.</p>",
+        ),
+        (
+            "<p>Costs $10 #1 [sic] &lt;new&gt; @gft = 50% off_now</p><hr><p>Content warning: strobe</p>",
+            "Costs \\$10 \\#1 \\[sic\\] \\<new\\> \\@gft \\= 50% off\\_now\n\n#line(length: 100%)\n\nContent warning: strobe",
+            "Costs $10 \\#1 \\[sic\\] \\<new\\> @gft = 50% off\\_now\n\n---\n\nContent warning: strobe",
+            "Costs $10 #1 [sic] <new> @gft = 50% off_now\n\n----\n\nContent warning: strobe",
+            "<p>Costs $10 #1 [sic] &lt;new&gt; @gft = 50% off_now</p>\n<hr/>\n<p>Content warning: strobe</p>",
+        ),
+        (
+            "<ul><li>Q&amp;A with the director</li><li>Intro by <i>the curator</i></li></ul>",
+            "- Q&A with the director\n- Intro by #emph[the curator]",
+            "- Q&A with the director\n- Intro by *the curator*",
+            "- Q&A with the director\n- Intro by the curator",
+            "<ul><li>Q&amp;A with the director</li><li>Intro by <em>the curator</em></li></ul>",
+        ),
+        (
+            "First line\r\n\r\n<style>p { color: red }</style><!-- a comment -->Second  line<p>1. Not a list",
+            "First line Second line\n\n1\\. Not a list",
+            "First line Second line\n\n1\\. Not a list",
+            "First line Second line\n\n1. Not a list",
+            "<p>First line Second line</p>\n<p>1. Not a list</p>",
+        ),
+    ];
+
+    #[test]
+    fn test_renderers() {
+        for (html, typst, markdown, plain, to_html) in SYNOPSES {
+            let rich = RichText::parse(html);
+            assert_eq!(*typst, rich.to_typst(), "typst for {}", html);
+            assert_eq!(*markdown, rich.to_markdown(), "markdown for {}", html);
+            assert_eq!(*plain, rich.to_plain(), "plain for {}", html);
+            assert_eq!(*to_html, rich.to_html(), "html for {}", html);
+        }
+    }
+
+    #[test]
+    fn test_from_plain() {
+        let rich = RichText::from_plain("One <b>\r\ntwo\n\n\n Three & four ");
+        assert_eq!(
+            "<p>One &lt;b&gt;<br/>two</p>\n<p>Three &amp; four</p>",
+            rich.to_html()
+        );
+        assert_eq!(RichText::default(), RichText::from_plain("\n\n"));
+    }
+
+    #[test]
+    fn test_parse() {
+        // Misnested tags close where their parent does, and stray closing tags go
+        assert_eq!(
+            vec![Block::Paragraph(vec![
+                Inline::Strong(vec![text("a "), Inline::Emph(vec![text("b")])]),
+                text(" c")
+            ])],
+            RichText::parse("<b>a <i>b</b> c</i></span>").blocks
+        );
+        // A < that isn't a tag is text
+        assert_eq!(
+            vec![Block::Paragraph(vec![text("1 < 2 and 3 <4")])],
+            RichText::parse("1 < 2 and 3 <4").blocks
+        );
+        assert_eq!(
+            vec![Block::Paragraph(vec![text("x"), Inline::Break, text("y")])],
+            RichText::parse("<br>x <br/> y<br>").blocks
+        );
+        assert!(RichText::parse("<p> </p><p>&nbsp;</p>").blocks.len() == 1);
+        assert_eq!(RichText::default(), RichText::parse("<p>  </p>"));
+    }
+
+    #[test]
+    fn test_typst_compiles() {
+        let markup = SYNOPSES
+            .iter()
+            .map(|(html, ..)| RichText::parse(html).to_typst())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let dir = std::env::temp_dir().join(format!("summary-rich-text-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("synopsis.typ"),
+            "#import sys: inputs\n#eval(inputs.version, mode: \"markup\")\n",
+        )
+        .unwrap();
+        let mut inputs = typst::foundations::Dict::new();
+        inputs.insert(
+            "version".into(),
+            typst::foundations::IntoValue::into_value(markup),
+        );
+        let pdf = crate::templates::compile_pdf(Some(&dir), "synopsis.typ", inputs);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(pdf.is_ok(), "{}", pdf.err().unwrap());
+    }
+}
@@ -0,0 +1,211 @@
+use crate::RenderError;
+use include_dir::{include_dir, Dir};
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use typst::diag::{FileError, FileResult, Severity, SourceDiagnostic};
+use typst::foundations::{Bytes, Dict};
+use typst::layout::PagedDocument;
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst_as_lib::file_resolver::FileResolver;
+use typst_as_lib::{TypstAsLibError, TypstEngine};
+
+// Plain copies of the templates, so errors can be reported by file and line, and so
+// templates in an override directory can import or include files they don't replace.
+static TEMPLATES: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/templates");
+static FONTS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/fonts");
+
+/// A compile error located in a template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    // 1-based, 0 if typst didn't say where
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// Files in `dir`, if there is one, win, anything else comes from the embedded templates.
+struct OverrideResolver {
+    dir: Option<PathBuf>,
+}
+
+impl OverrideResolver {
+    fn read(&self, id: FileId) -> FileResult<Vec<u8>> {
+        let not_found = || FileError::NotFound(id.vpath().as_rootless_path().into());
+        if id.package().is_some() {
+            return Err(not_found());
+        }
+        if let Some(dir) = &self.dir
+            && let Some(path) = id.vpath().resolve(dir)
+            && let Ok(data) = std::fs::read(path)
+        {
+            return Ok(data);
+        }
+        TEMPLATES
+            .get_file(id.vpath().as_rootless_path())
+            .map(|f| f.contents().to_vec())
+            .ok_or_else(not_found)
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        let data = self.read(id)?;
+        let text = String::from_utf8(data).map_err(|_| FileError::InvalidUtf8)?;
+        Ok(Source::new(id, text))
+    }
+
+    fn diagnostic(&self, diag: &SourceDiagnostic) -> Diagnostic {
+        let mut message = diag.message.to_string();
+        for hint in &diag.hints {
+            message.push_str(&format!(" (hint: {})", hint));
+        }
+        let Some(id) = diag.span.id() else {
+            return Diagnostic {
+                file: "".to_string(),
+                line: 0,
+                message,
+            };
+        };
+        let file = match id.package() {
+            Some(package) => format!("{}/{}", package, id.vpath().as_rootless_path().display()),
+            None => id.vpath().as_rootless_path().display().to_string(),
+        };
+        let line = self
+            .source(id)
+            .ok()
+            .and_then(|s| {
+                s.range(diag.span)
+                    .and_then(|r| s.lines().byte_to_line(r.start))
+            })
+            .map(|l| l + 1)
+            .unwrap_or(0);
+        Diagnostic {
+            file,
+            line,
+            message,
+        }
+    }
+}
+
+impl FileResolver for OverrideResolver {
+    fn resolve_binary(&self, id: FileId) -> FileResult<Cow<'_, Bytes>> {
+        Ok(Cow::Owned(Bytes::new(self.read(id)?)))
+    }
+
+    fn resolve_source(&self, id: FileId) -> FileResult<Cow<'_, Source>> {
+        Ok(Cow::Owned(self.source(id)?))
+    }
+}
+
+/// Compile `entry` with the templates in `dir`, if given, overriding the embedded ones.
+/// Packages come from the typst package cache, downloading them if needed.
+pub fn compile_pdf<T: Into<Dict>>(
+    dir: Option<&Path>,
+    entry: &str,
+    inputs: T,
+) -> Result<Vec<u8>, RenderError> {
+    let resolver = OverrideResolver {
+        dir: dir.map(Path::to_path_buf),
+    };
+    let main = resolver
+        .source(FileId::new(None, VirtualPath::new(entry)))
+        .map_err(|e| RenderError::Typst(entry.to_string(), format!("{}", e)))?;
+    let engine = TypstEngine::builder()
+        .main_file(main)
+        .add_file_resolver(OverrideResolver {
+            dir: dir.map(Path::to_path_buf),
+        })
+        .with_package_file_resolver()
+        .fonts(FONTS.files().map(|f| f.contents()))
+        .build();
+    let compiled = engine
+        .compile_with_input::<_, PagedDocument>(inputs)
+        .output
+        .map_err(|e| match e {
+            TypstAsLibError::TypstSource(diagnostics) => RenderError::Compile(
+                diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Severity::Error)
+                    .map(|d| resolver.diagnostic(d))
+                    .collect(),
+            ),
+            other => RenderError::Typst(entry.to_string(), format!("{}", other)),
+        })?;
+    typst_pdf::pdf(&compiled, &typst_pdf::PdfOptions::default())
+        .map_err(|e| RenderError::Typst(entry.to_string(), format!("{:?}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use typst::foundations::IntoValue;
+
+    fn inputs() -> Dict {
+        let mut dict = Dict::new();
+        dict.insert("version".into(), "1".into_value());
+        dict
+    }
+
+    #[test]
+    fn test_override() {
+        let dir = std::env::temp_dir().join(format!("summary-templates-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("broken.typ"),
+            "= Title\n\n#let x = \n#undefined_thing\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("fine.typ"),
+            "#import sys: inputs\n#image(\"banner.jpg\")\nv#inputs.version\n",
+        )
+        .unwrap();
+
+        let Err(RenderError::Compile(diagnostics)) =
+            compile_pdf(Some(&dir), "broken.typ", inputs())
+        else {
+            panic!("broken.typ compiled");
+        };
+        assert_eq!("broken.typ", diagnostics[0].file);
+        assert_eq!(3, diagnostics[0].line);
+
+        // banner.jpg isn't in the override directory so comes from the embedded templates
+        let pdf = compile_pdf(Some(&dir), "fine.typ", inputs()).unwrap();
+        assert_eq!(b"%PDF", &pdf[0..4]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn compile_error(dir: Option<&Path>, inputs: Dict) -> String {
+        match compile_pdf(dir, "brochure.typ", inputs) {
+            Err(e @ RenderError::Compile(_)) => e.to_string(),
+            Err(e) => panic!("{}", e),
+            Ok(_) => panic!("brochure.typ compiled"),
+        }
+    }
+
+    #[test]
+    fn test_embedded_errors() {
+        // The embedded brochure reads the version on line 5
+        assert!(compile_error(None, Dict::new()).starts_with("brochure.typ:5: "));
+
+        // The embedded brochure with a mistake after where the version is read
+        let dir = std::env::temp_dir().join(format!("summary-embedded-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text = TEMPLATES
+            .get_file("brochure.typ")
+            .unwrap()
+            .contents_utf8()
+            .unwrap();
+        let mut lines = text.lines().collect::<Vec<_>>();
+        lines.insert(5, "#undefined_thing");
+        std::fs::write(dir.join("brochure.typ"), lines.join("\n")).unwrap();
+        let message = compile_error(Some(&dir), inputs());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(message.starts_with("brochure.typ:6: unknown variable: undefined_thing"));
+    }
+}
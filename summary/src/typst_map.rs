@@ -1,65 +1,101 @@
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::{Deref, DerefMut};
 use typst::foundations as t;
 use typst_bake::__internal::typst::foundations as tb;
 use typst_library::foundations as tl;
 
-#[derive(Default, Serialize, Deserialize)]
-pub struct TypstMap<T: tb::IntoValue>(HashMap<String, T>);
+/// The order the keys reach the template in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Order {
+    #[default]
+    Insertion,
+    Sorted,
+}
+
+/// A string keyed map that becomes a typst dictionary.  Keys keep the order they were
+/// inserted in unless the map is sorted, so identical data always gives an identical
+/// dictionary.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TypstMap<T: tb::IntoValue> {
+    map: IndexMap<String, T>,
+    #[serde(skip)]
+    order: Order,
+}
 
 impl<T: tb::IntoValue> DerefMut for TypstMap<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.map
     }
 }
 impl<T: tb::IntoValue> Deref for TypstMap<T> {
-    type Target = HashMap<String, T>;
+    type Target = IndexMap<String, T>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.map
     }
 }
 
+// A HashMap has no order of its own worth keeping
 impl<T: tb::IntoValue> From<HashMap<String, T>> for TypstMap<T> {
     fn from(value: HashMap<String, T>) -> Self {
-        TypstMap(value)
+        TypstMap {
+            map: value.into_iter().collect(),
+            order: Order::Sorted,
+        }
+    }
+}
+
+impl<T: tb::IntoValue> From<BTreeMap<String, T>> for TypstMap<T> {
+    fn from(value: BTreeMap<String, T>) -> Self {
+        TypstMap {
+            map: value.into_iter().collect(),
+            order: Order::Insertion,
+        }
     }
 }
 
 impl<T: tb::IntoValue> t::IntoValue for TypstMap<T> {
     fn into_value(self) -> tb::Value {
-        let d = {
-            #[allow(unused_mut)]
-            let mut map = tl::IndexMap::default();
-            for (k, v) in self.0.into_iter() {
-                map.insert(
-                    k.into(),
-                    tl::IntoValue::into_value(tb::IntoValue::into_value(v)),
-                );
-            }
-            tl::Dict::from(map)
-        };
-        tb::Value::Dict(d)
+        tb::Value::Dict(self.into_dict())
     }
 }
 
 impl<T: tb::IntoValue> TypstMap<T> {
+    /// An empty map whose keys are sorted on the way to typst.
+    pub fn sorted() -> Self {
+        TypstMap {
+            map: IndexMap::default(),
+            order: Order::Sorted,
+        }
+    }
+
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    pub fn set_order(&mut self, order: Order) {
+        self.order = order;
+    }
+
     #[inline]
     #[must_use]
     pub fn into_dict(self) -> tb::Dict {
-        {
-            #[allow(unused_mut)]
-            let mut map = tl::IndexMap::default();
-            for (k, v) in self.0.into_iter() {
-                map.insert(
-                    k.into(),
-                    tl::IntoValue::into_value(tb::IntoValue::into_value(v)),
-                );
-            }
-
-            tl::Dict::from(map)
+        let mut entries = self.map;
+        if self.order == Order::Sorted {
+            entries.sort_keys();
         }
+        #[allow(unused_mut)]
+        let mut map = tl::IndexMap::default();
+        for (k, v) in entries.into_iter() {
+            map.insert(
+                k.into(),
+                tl::IntoValue::into_value(tb::IntoValue::into_value(v)),
+            );
+        }
+        tl::Dict::from(map)
     }
 }
 
@@ -71,10 +107,38 @@ impl<T: t::IntoValue> From<TypstMap<T>> for t::Dict {
 
 impl From<Vec<(&str, &str)>> for TypstMap<String> {
     fn from(value: Vec<(&str, &str)>) -> Self {
-        value
-            .iter()
-            .map(|(a, b)| (a.to_string(), b.to_string()))
-            .collect::<HashMap<String, String>>()
-            .into()
+        TypstMap {
+            map: value
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect(),
+            order: Order::Insertion,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(map: TypstMap<u32>) -> Vec<String> {
+        map.into_dict().iter().map(|(k, _)| k.to_string()).collect()
+    }
+
+    #[test]
+    fn test_order() {
+        let mut map = TypstMap::default();
+        map.insert("b".to_string(), 1);
+        map.insert("c".to_string(), 2);
+        map.insert("a".to_string(), 3);
+        assert_eq!(vec!["b", "c", "a"], keys(map.clone()));
+        map.set_order(Order::Sorted);
+        assert_eq!(vec!["a", "b", "c"], keys(map));
+
+        let hash = (0..20)
+            .map(|i| (format!("k{:02}", i), i))
+            .collect::<HashMap<_, _>>();
+        let sorted = (0..20).map(|i| format!("k{:02}", i)).collect::<Vec<_>>();
+        assert_eq!(sorted, keys(hash.into()));
     }
 }
@@ -1,6 +1,7 @@
 #import "@preview/in-dexter:0.7.2": *
 #import "@preview/wrap-it:0.1.1": wrap-content
 #import sys: inputs
+#import "grid.typ": generate_summary
 #let version = inputs.version 
 #set page(
   paper: "a4",
@@ -20,7 +21,7 @@
   )
 
 #v(1fr)
-#align(center)[#image(bytes(inputs.banner), width: 100%)]
+#align(center)[#image("banner.jpg", width: 100%)]
 #v(1fr)
 #pagebreak()
 #set page(flipped: true)
@@ -28,6 +29,13 @@
 #counter(page).update(1) 
 
 #set page(flipped: true)
+// The day grids, when asked for, with each block linking to the film's listing
+#let dayGrid = inputs.at("grid", default: none)
+#if dayGrid != none {
+  set text(font: "Source Serif 4", size: 0.8em)
+  set par(justify: false, leading: 0.55em)
+  generate_summary(dayGrid)
+}
 #set par(justify: false)
 #set par(leading: 0.55em)
 
@@ -35,19 +43,51 @@
   flipped: false)
 #pagebreak()
 
+// What changed since the last published version, when there's one to compare with
+#let changes = inputs.at("changes", default: none)
+#let changeList(title, items, describe) = if items.len() > 0 [
+  == #title
+  #for c in items [- #describe(c)]
+]
+#let filmRef(c) = if c.id == "" [#c.name] else [#link(label(c.id))[#c.name]]
+#if changes != none {
+  [= What changed since v#changes.since]
+  set text(size: 0.9em)
+  changeList("New films", changes.new_films, f => filmRef(f))
+  changeList("Moved", changes.moved, c => [#filmRef(c): #c.was #sym.arrow #c.now])
+  changeList("Extra showings", changes.added, c => [#filmRef(c): #c.now])
+  changeList("Cancelled", changes.cancelled, c => [#filmRef(c): #strike[#c.was]])
+  pagebreak()
+}
+
+// What the symbols on the showing lines mean
+#let badgeKey = inputs.at("badge_key", default: ())
+#if badgeKey.len() > 0 {
+  text(size: 0.8em)[*Key:* #badgeKey.map(b => [*#b.symbol* #b.name]).join([, ])]
+}
 
 #set par(justify: true)
 #set par(leading: 0.35em)
 #columns(2)[
+// A card in the strand colour stands in for a missing or unreadable poster
+#let poster(film) = if film.poster_bytes == none {
+  let colour = if film.colour == "" { "808080" } else { film.colour }
+  box(width: 75pt, height: 112.5pt, fill: rgb(colour + "50"), stroke: 1pt + black, radius: 3pt, inset: 6pt)[
+    #align(center + horizon)[#text(weight: "bold")[#film.name]]]
+} else {
+  image(bytes(film.poster_bytes), width: 75pt)
+}
 #let showFilm(film) = block(breakable: false)[
 #index[#film.name]
 #index[#film.sortname]
 = #film.name #label(film.id) (#film.rating) \
-#if film.strand != "" [ #film.strand\ ]
+#if film.strand != "" [ #film.strand #for s in film.secondary [ #box(width: 0.6em, height: 0.6em, fill: rgb(s.colour + "50"), stroke: 0.5pt + black) #text(size: 0.8em)[#s.name]]\ ]
 #text(size: 0.8em)[
 #for s in film.showings [
-//#link(label("s" + s.datetime.slice(0,10)))
-[#s.date, #s.time - #s.screen] \
+#let when = [#s.date, #s.time - #s.screen]
+#if dayGrid == none [#when] else [#link(label("s" + s.datetime.slice(0,10)))[#when]]
+#for b in s.badges [ *#b.symbol*]
+#if s.changed [#text(weight: "bold", fill: red)[changed]] \
 ]]
 
         #let sidebar = ()
@@ -65,6 +105,18 @@ sidebar.push(film.directed_by) }
 #if film.rating_reason != ""  {
         sidebar.push("Rating notes:")
         sidebar.push(film.rating_reason)} 
+#if film.made != "" {
+        sidebar.push("Made:")
+        sidebar.push(film.made) }
+#if film.spoken != "" {
+        sidebar.push("Language:")
+        sidebar.push(film.spoken) }
+#if film.certificate != "" {
+        sidebar.push("Certificate:")
+        sidebar.push(film.certificate) }
+#if film.trailer != "" {
+        sidebar.push("Trailer:")
+        sidebar.push(link(film.trailer)[Youtube]) }
 
     #sidebar.push("")
     #sidebar.push("")
@@ -74,7 +126,7 @@ sidebar.push(film.directed_by) }
     #let tags = text(size: 0.6em)[#grid(columns: (6em,auto),gutter: 3pt,..sidebar)]
 #let synmark = eval(syntext,mode:"markup")
     //#sidebar.push(text(size: 0.7em)[ #synmark ])
-    #wrap-content(image(bytes(film.poster_bytes),width: 75pt),text(size: 0.7em)[#tags #text(size: 1.1em)[#synmark]],column-gutter: 15pt)
+    #wrap-content(poster(film),text(size: 0.7em)[#tags #text(size: 1.1em)[#synmark]],column-gutter: 15pt)
 
 #v(1em)
 ]
@@ -89,3 +141,21 @@ sidebar.push(film.directed_by) }
 #columns(3)[
   #make-index(title: none, outlined: true, use-page-counter:true)
 ]
+
+// Films by strand, director and genre, each linking back to the film's entry
+#let indexFilm(f) = [
+  #link(label(f.id))[#f.name] #box(width: 1fr, repeat[.])
+  #context counter(page).at(label(f.id)).first() \
+]
+#let indexGroup(g) = block(breakable: true)[
+  #if g.colour != "" [
+    #box(width: 0.7em, height: 0.7em, fill: rgb(g.colour), stroke: 0.5pt + black)
+  ]
+  #text(weight: "bold")[#g.name] \
+  #text(size: 0.8em)[#for f in g.films { indexFilm(f) }]
+]
+#for i in inputs.at("indexes", default: ()) [
+  #pagebreak()
+  #heading(outlined: true)[#i.title]
+  #columns(3)[#for g in i.groups { indexGroup(g) }]
+]
@@ -0,0 +1,23 @@
+#import sys: inputs
+#import "grid.typ": generate_summary
+#let version = inputs.version
+#let current_filter=state("current_filter","")
+#set page(
+  flipped: true,
+  paper: "a4",
+  margin: (inside: 2.5cm, outside: 2cm, y: 1.75cm),
+  footer: context [ GFF 2026 v#version#current_filter.get() #h(1fr) #counter(page).display("1") ],
+  number-align: center,
+  )
+#set par(justify: false,leading: 0.55em)
+#set text(font: "Source Serif 4",size: 0.8em)
+#if inputs.full {
+current_filter.update("")
+generate_summary(inputs.summary)
+}
+#for (i,filter) in inputs.filters.enumerate() {
+if inputs.full or i > 0 { pagebreak() }
+current_filter.update(" for "+filter.title)
+counter(page).update(1)
+generate_summary(inputs.summary,suffix: ("for "+ filter.title),filter: filter.people)
+}
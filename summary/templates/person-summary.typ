@@ -0,0 +1,14 @@
+#import sys: inputs
+#import "grid.typ": person_summary
+#let version = inputs.version
+#set page(
+  flipped: true,
+  paper: "a4",
+  margin: (inside: 2.5cm, outside: 2cm, y: 1.75cm),
+  footer: context [ GFF 2026 v#version #h(1fr) #counter(page).display("1") ],
+  number-align: center,
+  )
+#set par(justify: false,leading: 0.55em)
+#set text(font: "Source Serif 4",size: 0.8em)
+#counter(page).update(1)
+#person_summary(inputs.summary, names: inputs.names)